@@ -0,0 +1,129 @@
+
+use std::fmt::Display;
+use regex::Regex;
+use time::Date;
+
+use std::sync::LazyLock;
+
+use super::{Wbs, Plant, Quantity};
+
+static STOCK_DEFICIT    : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)deficit of (?:(PS|project)\b)?").expect("Failed to build STOCK_DEFICIT regex") );
+static NO_ORDER         : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)order \S* ?(?:does not exist|not found)|no (?:production )?order").expect("Failed to build NO_ORDER regex") );
+static ORDER_CLOSED     : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(?:TECO|CLSD|DLFL)\b|technically completed").expect("Failed to build ORDER_CLOSED regex") );
+static MATERIAL_LOCKED  : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)(?:material|plant data).*(?:locked|blocked)|is currently (?:being processed|locked)").expect("Failed to build MATERIAL_LOCKED regex") );
+static PERIOD_CLOSED    : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)posting (?:only )?possible in periods?|period .* (?:is )?(?:not open|closed)").expect("Failed to build PERIOD_CLOSED regex") );
+static NOT_MAINTAINED   : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)(?:not (?:maintained|created|defined|extended)|does not exist) (?:in|for) (?:plant|storage location)").expect("Failed to build NOT_MAINTAINED regex") );
+
+/// A goods movement error from SAP transaction COGI
+#[derive(Debug, Clone, Serialize)]
+pub struct CogiError {
+    /// material number (usually the plate)
+    pub matl: String,
+    /// plant the movement was posted in
+    pub plant: Plant,
+    /// storage location
+    pub loc: String,
+    /// WBS element of the movement (if project stock)
+    pub wbs: Wbs,
+    /// quantity of the movement
+    pub qty: Quantity,
+    /// error message text
+    pub message: String,
+    /// date the error was logged
+    pub date: Option<Date>,
+}
+
+impl CogiError {
+    /// classify the error message into a [`CogiCause`]
+    pub fn cause(&self) -> CogiCause {
+        CogiCause::classify(&self.message)
+    }
+}
+
+/// The cause of a COGI error, as classified from the message text
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum CogiCause {
+    /// Deficit of project stock (material not in stock under the WBS)
+    WbsStockDeficit,
+    /// Deficit of plant (unrestricted) stock
+    StockDeficit,
+    /// No order exists to post against
+    NoOrder,
+    /// Order is technically complete, closed or flagged for deletion
+    OrderClosed,
+    /// Material is locked by another user or process
+    MaterialLocked,
+    /// Posting period is closed
+    PeriodClosed,
+    /// Material is not maintained in the plant or storage location
+    MaterialNotMaintained,
+    /// Message did not match any known cause
+    Other,
+}
+
+impl CogiCause {
+    /// classify a COGI error message text
+    pub fn classify(message: &str) -> Self {
+        if let Some(caps) = STOCK_DEFICIT.captures(message) {
+            match caps.get(1) {
+                Some(_) => Self::WbsStockDeficit,
+                None    => Self::StockDeficit,
+            }
+        }
+        else if PERIOD_CLOSED.is_match(message)   { Self::PeriodClosed }
+        else if MATERIAL_LOCKED.is_match(message) { Self::MaterialLocked }
+        else if NOT_MAINTAINED.is_match(message)  { Self::MaterialNotMaintained }
+        else if ORDER_CLOSED.is_match(message)    { Self::OrderClosed }
+        else if NO_ORDER.is_match(message)        { Self::NoOrder }
+        else                                      { Self::Other }
+    }
+}
+
+impl Display for CogiCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use CogiCause::*;
+
+        let name = match self {
+            WbsStockDeficit       => "Deficit of WBS stock",
+            StockDeficit          => "Deficit of plant stock",
+            NoOrder               => "No order",
+            OrderClosed           => "Order closed",
+            MaterialLocked        => "Material locked",
+            PeriodClosed          => "Period closed",
+            MaterialNotMaintained => "Material not maintained",
+            Other                 => "Other",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_are_classified() {
+        let messages = [
+            ("Deficit of PS Project stock unrestricted 2 EA : 50/50W-0500 D-1200001-10002 HS01 PROD", CogiCause::WbsStockDeficit),
+            ("Deficit of project stock 12.5 FT2 : 50/50W-0500 HS01 PROD",                           CogiCause::WbsStockDeficit),
+            ("Deficit of SL Unrestricted-use 120.5 FT2 : 50/50W-0500 HS01 PROD",                     CogiCause::StockDeficit),
+            ("Order 1040123 does not exist",                                                         CogiCause::NoOrder),
+            ("Order not found",                                                                      CogiCause::NoOrder),
+            ("Status TECO is active (ORD 1040123)",                                                  CogiCause::OrderClosed),
+            ("Status DLFL is active (ORD 1040123)",                                                  CogiCause::OrderClosed),
+            ("Order 1040123 is technically completed",                                               CogiCause::OrderClosed),
+            ("Plant data of material 50/50W-0500 is locked by user JSMITH",                          CogiCause::MaterialLocked),
+            ("Material 50/50W-0500 is currently being processed by JSMITH",                          CogiCause::MaterialLocked),
+            ("Posting only possible in periods 2026/09 and 2026/10 in company code 1000",            CogiCause::PeriodClosed),
+            ("Period 010/2026 is not open",                                                          CogiCause::PeriodClosed),
+            ("Material 50/50W-0500 not maintained in plant HS02",                                    CogiCause::MaterialNotMaintained),
+            ("Material 50/50W-0500 not extended for storage location HS01 K2",                       CogiCause::MaterialNotMaintained),
+            ("Enter a cost center",                                                                  CogiCause::Other),
+        ];
+
+        for (message, cause) in messages {
+            assert_eq!(CogiCause::classify(message), cause, "{}", message);
+        }
+    }
+}
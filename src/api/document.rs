@@ -0,0 +1,63 @@
+
+use time::Date;
+
+use super::{Wbs, Plant, Quantity};
+use crate::Error;
+
+/// SAP goods movement type
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize)]
+pub enum MovementType {
+    /// Goods receipt for order (101)
+    GoodsReceipt,
+    /// Goods issue for order (261)
+    GoodsIssue,
+    /// Reversal of goods issue for order (262)
+    GoodsIssueReversal,
+    /// Receipt of by-product (531)
+    ByProduct,
+}
+
+impl MovementType {
+    /// the SAP movement type code
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::GoodsReceipt       => 101,
+            Self::GoodsIssue         => 261,
+            Self::GoodsIssueReversal => 262,
+            Self::ByProduct          => 531,
+        }
+    }
+}
+
+impl TryFrom<u32> for MovementType {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            101 => Ok( Self::GoodsReceipt ),
+            261 => Ok( Self::GoodsIssue ),
+            262 => Ok( Self::GoodsIssueReversal ),
+            531 => Ok( Self::ByProduct ),
+            _ => Err( Error::parse("movement type", value) )
+        }
+    }
+}
+
+/// A material document line from SAP transaction MB51
+#[derive(Debug, Clone, Serialize)]
+pub struct MaterialDocument {
+    /// movement type
+    pub movement: MovementType,
+    /// material number
+    pub matl: String,
+    /// plant
+    pub plant: Plant,
+    /// WBS element (if project stock)
+    pub wbs: Wbs,
+    /// quantity moved (always positive, direction is given by the movement type)
+    pub qty: Quantity,
+    /// posting date
+    pub date: Option<Date>,
+    /// document reference (the Sigmanest program for confirmations)
+    pub reference: String,
+}
@@ -1,10 +1,18 @@
 
 //! common api
 
+mod cogi;
+mod document;
 mod order;
 mod plant;
+mod stock;
+mod uom;
 mod wbs;
 
+pub use cogi::{CogiCause, CogiError};
+pub use document::{MaterialDocument, MovementType};
 pub use order::{Order, OrderData};
-pub use plant::Plant;
+pub use plant::{BurnPlant, Plant};
+pub use stock::StockItem;
+pub use uom::{Area, Qty, Quantity, Uom};
 pub use wbs::Wbs;
@@ -1,8 +1,10 @@
 
-use super::{Wbs, Plant};
+use super::{Wbs, Plant, Qty};
+use crate::{Error, Result};
 
 /// SAP order type
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
 pub enum Order {
     /// Planned order
     PlannedOrder(OrderData),
@@ -12,24 +14,31 @@ pub enum Order {
 
 impl Order {
     /// creates a new Order from a given type and data
-    pub fn new(order_type: &str, data: OrderData) -> Self {
+    pub fn new(order_type: &str, data: OrderData) -> Result<Self> {
         match order_type {
-            "PP01" => Order::ProductionOrder(data),
-            "PR"   => Order::PlannedOrder(data),
-            _ => unreachable!()
+            "PP01" => Ok( Order::ProductionOrder(data) ),
+            "PR"   => Ok( Order::PlannedOrder(data) ),
+            _ => Err( Error::parse("order type", order_type) )
+        }
+    }
+
+    /// the data of the order, regardless of type
+    pub fn data(&self) -> &OrderData {
+        match self {
+            Order::PlannedOrder(data) | Order::ProductionOrder(data) => data
         }
     }
 }
 
 /// Data for any given order
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct OrderData {
     /// order number
     pub id: u32,
     /// piece mark
     pub mark: String,
     /// order quantity
-    pub qty: u32,
+    pub qty: Qty,
     /// WBS element for the order
     pub wbs: Wbs,
     /// plant (Lancaster or Williamsport)
@@ -39,11 +48,11 @@ pub struct OrderData {
 impl OrderData {
     /// Apply (reduce) the order quanity by a given amount.
     /// Amount being reduced must not be greater than the order quantity.
-    pub fn apply_qty(&mut self, qty: u32) {
-        if self.qty < qty {
-            panic!("Cannot apply qty({}) greater than PlannedOrder({})", qty, self.qty);
-        }
+    pub fn apply_qty(&mut self, qty: Qty) -> Result<()> {
+        self.qty = self.qty
+            .checked_sub(qty)
+            .ok_or( Error::Allocation { order: self.id, requested: qty, available: self.qty } )?;
 
-        self.qty -= qty;
+        Ok(())
     }
 }
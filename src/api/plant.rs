@@ -1,6 +1,10 @@
 
+use std::fmt::Display;
+
+use crate::Error;
+
 /// represents an SAP plant
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub enum Plant {
     /// Lancaster (HS01)
     #[serde(rename = "HS01")]
@@ -10,18 +14,92 @@ pub enum Plant {
     Williamsport
 }
 
-impl From<String> for Plant {
-    fn from(value: String) -> Self {
-        Self::from(value.as_str())
+impl TryFrom<String> for Plant {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
     }
 }
 
-impl From<&str> for Plant {
-    fn from(value: &str) -> Self {
+impl TryFrom<&str> for Plant {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
-            "HS01" => Self::Lancaster,
-            "HS02" => Self::Williamsport,
-            _ => panic!("Unexpected plant string <{}>", value)
+            "HS01" => Ok( Self::Lancaster ),
+            "HS02" => Ok( Self::Williamsport ),
+            _ => Err( Error::parse("plant", value) )
+        }
+    }
+}
+
+impl Display for Plant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lancaster    => write!(f, "HS01"),
+            Self::Williamsport => write!(f, "HS02"),
         }
     }
-}
\ No newline at end of file
+}
+
+/// The plant a part was burned at, as given by Sigmanest
+///
+/// Sigmanest derives the plant from the machine name, so it is kept as given
+/// if it is not an SAP plant.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum BurnPlant {
+    /// an SAP plant
+    Known(Plant),
+    /// a plant SAP does not have
+    Unknown(String),
+}
+
+impl BurnPlant {
+    /// the SAP plant, if known
+    pub fn known(&self) -> Option<&Plant> {
+        match self {
+            Self::Known(plant) => Some(plant),
+            Self::Unknown(_)   => None,
+        }
+    }
+}
+
+impl From<&str> for BurnPlant {
+    fn from(value: &str) -> Self {
+        match Plant::try_from(value) {
+            Ok(plant) => Self::Known(plant),
+            Err(_)    => Self::Unknown(value.into()),
+        }
+    }
+}
+
+impl From<Plant> for BurnPlant {
+    fn from(value: Plant) -> Self {
+        Self::Known(value)
+    }
+}
+
+impl Display for BurnPlant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Known(plant)   => write!(f, "{}", plant),
+            Self::Unknown(plant) => write!(f, "{}", plant),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burn_plants_keep_unknown_plants() {
+        assert_eq!(BurnPlant::from("HS02"), BurnPlant::Known(Plant::Williamsport));
+        assert_eq!(BurnPlant::from("HS09"), BurnPlant::Unknown("HS09".into()));
+
+        assert_eq!(serde_json::to_string(&BurnPlant::from("HS01")).unwrap(), r#""HS01""#);
+        assert_eq!(serde_json::from_str::<BurnPlant>(r#""HS09""#).unwrap().to_string(), "HS09");
+    }
+}
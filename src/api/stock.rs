@@ -0,0 +1,17 @@
+
+use super::{Wbs, Plant, Area};
+
+/// A line of SAP stock (MB52/MMBE)
+#[derive(Debug, Clone, Serialize)]
+pub struct StockItem {
+    /// material number
+    pub matl: String,
+    /// plant the stock is in
+    pub plant: Plant,
+    /// storage location
+    pub loc: String,
+    /// WBS element for project stock (special stock `Q`), otherwise [`Wbs::None`]
+    pub wbs: Wbs,
+    /// unrestricted-use quantity
+    pub qty: Area,
+}
@@ -0,0 +1,302 @@
+
+use std::fmt::Display;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use crate::{Error, Result};
+
+/// square inches in a square foot
+const IN2_PER_FT2: f64 = 144.0;
+
+/// SAP unit of measure
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize)]
+pub enum Uom {
+    /// Each (pieces)
+    #[serde(rename = "EA")]
+    Each,
+    /// Square inches
+    #[serde(rename = "IN2")]
+    SqIn,
+    /// Square feet
+    #[serde(rename = "FT2")]
+    SqFt,
+    /// Pounds
+    #[serde(rename = "LB")]
+    Pound,
+}
+
+impl Uom {
+    /// if the unit is a unit of area
+    pub fn is_area(&self) -> bool {
+        matches!(self, Self::SqIn | Self::SqFt)
+    }
+}
+
+impl TryFrom<&str> for Uom {
+    type Error = Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value.trim() {
+            "EA"  | "PC"  => Ok( Self::Each  ),
+            "IN2"         => Ok( Self::SqIn  ),
+            "FT2"         => Ok( Self::SqFt  ),
+            "LB"          => Ok( Self::Pound ),
+            _ => Err( Error::parse("unit of measure", value) )
+        }
+    }
+}
+
+impl Display for Uom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Each  => write!(f, "EA"),
+            Self::SqIn  => write!(f, "IN2"),
+            Self::SqFt  => write!(f, "FT2"),
+            Self::Pound => write!(f, "LB"),
+        }
+    }
+}
+
+/// A piece count (EA)
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Qty(pub u32);
+
+impl Qty {
+    /// subtracts `rhs`, returning `None` if it would go negative
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+}
+
+impl From<u32> for Qty {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl TryFrom<i32> for Qty {
+    type Error = Error;
+
+    fn try_from(value: i32) -> std::result::Result<Self, Self::Error> {
+        u32::try_from(value)
+            .map(Self)
+            .map_err(|_| Error::parse("piece count", value))
+    }
+}
+
+impl TryFrom<f64> for Qty {
+    type Error = Error;
+
+    /// converts a float (such as an excel cell), erroring on fractional or negative counts
+    fn try_from(value: f64) -> std::result::Result<Self, Self::Error> {
+        if value.fract() != 0.0 || value < 0.0 || value > u32::MAX as f64 {
+            return Err( Error::parse("piece count", value) );
+        }
+
+        Ok( Self(value as u32) )
+    }
+}
+
+impl Add for Qty {
+    type Output = Self;
+
+    /// adds the counts, saturating at `u32::MAX` (subtraction is checked, see [`Qty::checked_sub`])
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl AddAssign for Qty {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Qty {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl Display for Qty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} EA", self.0)
+    }
+}
+
+/// An area of material, stored (and serialized) in IN2
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize)]
+pub struct Area(f64);
+
+impl Area {
+    /// area from square inches
+    pub fn in2(value: f64) -> Self {
+        Self(value)
+    }
+
+    /// area from square feet
+    pub fn ft2(value: f64) -> Self {
+        Self(value * IN2_PER_FT2)
+    }
+
+    /// area in square inches
+    pub fn as_in2(&self) -> f64 {
+        self.0
+    }
+
+    /// area in square feet
+    pub fn as_ft2(&self) -> f64 {
+        self.0 / IN2_PER_FT2
+    }
+
+    /// the larger of two areas
+    pub fn max(self, other: Self) -> Self {
+        Self(self.0.max(other.0))
+    }
+}
+
+impl Add for Area {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Area {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Area {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Area {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Area {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl Display for Area {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2} IN2", self.0)
+    }
+}
+
+/// A quantity as exported from SAP, in any unit of measure
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Quantity {
+    /// the quantity value
+    pub value: f64,
+    /// the unit of measure of the value
+    pub uom: Uom,
+}
+
+impl Quantity {
+    /// creates a quantity from a value and unit of measure
+    pub fn new(value: f64, uom: Uom) -> Self {
+        Self { value, uom }
+    }
+
+    /// the quantity as a piece count, erroring if it is not in EA or is fractional
+    pub fn as_qty(&self) -> Result<Qty> {
+        match self.uom {
+            Uom::Each => Qty::try_from(self.value),
+            _ => Err( Error::parse("piece count", self) )
+        }
+    }
+
+    /// the quantity as an area, erroring if it is not in IN2 or FT2
+    pub fn as_area(&self) -> Result<Area> {
+        match self.uom {
+            Uom::SqIn => Ok( Area::in2(self.value) ),
+            Uom::SqFt => Ok( Area::ft2(self.value) ),
+            _ => Err( Error::parse("area", self) )
+        }
+    }
+}
+
+impl From<Qty> for Quantity {
+    fn from(value: Qty) -> Self {
+        Self::new(value.0 as f64, Uom::Each)
+    }
+}
+
+impl From<Area> for Quantity {
+    fn from(value: Area) -> Self {
+        Self::new(value.as_in2(), Uom::SqIn)
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.value, self.uom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piece_counts_from_floats() {
+        assert_eq!(Qty::try_from(4.0).unwrap(), Qty(4));
+        assert_eq!(Qty::try_from(0.0).unwrap(), Qty(0));
+
+        for value in [2.5, -1.0, -0.5, f64::NAN, f64::INFINITY, u32::MAX as f64 + 1.0] {
+            assert!(Qty::try_from(value).is_err(), "{}", value);
+        }
+        assert!(Qty::try_from(-1i32).is_err());
+    }
+
+    #[test]
+    fn piece_counts_do_not_underflow() {
+        assert_eq!(Qty(5).checked_sub(Qty(3)), Some(Qty(2)));
+        assert_eq!(Qty(3).checked_sub(Qty(5)), None);
+    }
+
+    #[test]
+    fn piece_counts_do_not_overflow() {
+        let mut total = Qty(u32::MAX - 1);
+        total += Qty(5);
+
+        assert_eq!(total, Qty(u32::MAX));
+        assert_eq!([Qty(2), Qty(u32::MAX)].into_iter().sum::<Qty>(), Qty(u32::MAX));
+    }
+
+    #[test]
+    fn areas_convert_between_units() {
+        assert_eq!(Area::ft2(2.0).as_in2(), 288.0);
+        assert_eq!(Area::in2(72.0).as_ft2(), 0.5);
+
+        assert_eq!(Quantity::new(1.5, Uom::SqFt).as_area().unwrap(), Area::in2(216.0));
+        assert_eq!(Quantity::new(10.0, Uom::SqIn).as_area().unwrap(), Area::in2(10.0));
+        assert!(Quantity::new(10.0, Uom::Each).as_area().is_err());
+        assert!(Quantity::new(10.0, Uom::Pound).as_area().is_err());
+
+        assert_eq!(Quantity::from(Area::ft2(1.0)), Quantity::new(144.0, Uom::SqIn));
+    }
+
+    #[test]
+    fn quantities_as_piece_counts() {
+        assert_eq!(Quantity::new(3.0, Uom::Each).as_qty().unwrap(), Qty(3));
+        assert!(Quantity::new(3.5, Uom::Each).as_qty().is_err());
+        assert!(Quantity::new(3.0, Uom::SqFt).as_qty().is_err());
+
+        assert_eq!(Quantity::from(Qty(7)), Quantity::new(7.0, Uom::Each));
+        assert_eq!(Uom::try_from(" PC ").unwrap(), Uom::Each);
+        assert!(Uom::try_from("KG").is_err());
+    }
+}
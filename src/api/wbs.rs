@@ -1,7 +1,9 @@
 
 use std::fmt::{Display, Debug};
 use regex::Regex;
-use serde::{Deserializer, de::Error, Serialize, Deserialize};
+use serde::{Deserializer, de::Error as _, Serialize, Deserialize};
+
+use crate::Error;
 
 use std::sync::LazyLock;
 
@@ -10,9 +12,10 @@ static HD_WBS          : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"D-(\d{7
 static LEGACY_WBS      : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"S-(\d{7})-2-(\d{2})").expect("Failed to build LEGACY_WBS regex") );
 
 /// A type of SAP WBS element
-#[derive(Clone, Hash, PartialEq, PartialOrd)]
+#[derive(Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Wbs {
     /// No WBS element
+    #[default]
     None,
     /// Cost center WBS
     CostCenter {
@@ -39,27 +42,36 @@ pub enum Wbs {
 }
 
 impl Wbs {
+    /// the job number of a project WBS element
+    pub fn job(&self) -> Option<&str> {
+        match self {
+            Self::Hd { job, .. } | Self::Legacy { job, .. } => Some(job),
+            Self::CostCenter { .. } | Self::None => None
+        }
+    }
+
     /// update the WBS id for an HD WBS
-    pub fn set_id(mut self, new_id: u32) {
+    pub fn set_id(&mut self, new_id: u32) -> crate::Result<()> {
         match self {
-            Self::Hd { job: _, ref mut id } => *id = new_id,
+            Self::Hd { id, .. } => *id = new_id,
 
-            Self::CostCenter { .. } => panic!("Cannot assign an Id to a CostCenter Wbs"),
-            Self::Legacy { .. } => panic!("Cannot assign an Id to a Legacy Wbs"),
-            Self::None => panic!("Cannot assign Id to no Wbs")
+            Self::CostCenter { .. } |
+            Self::Legacy { .. } |
+            Self::None => return Err( Error::WbsOperation { operation: "set the HD id of", wbs: format!("{:?}", self) } )
         }
+
+        Ok(())
     }
 
     /// convert a WBS element into an HD WBS
-    pub fn into_hd_wbs(self, id: u32) -> Self {
+    pub fn into_hd_wbs(self, id: u32) -> crate::Result<Self> {
         match self {
-            Self::Hd { .. } => self,
-            Self::Legacy { job, shipment: _ } => Self::Hd { job, id },
+            Self::Hd { .. } => Ok( self ),
+            Self::Legacy { job, shipment: _ } => Ok( Self::Hd { job, id } ),
 
-            Self::CostCenter { .. } => panic!("Cannot convert CostCenter Wbs to an HD Wbs"),
-            Self::None => Self::None
+            Self::CostCenter { .. } => Err( Error::WbsOperation { operation: "make an HD WBS from", wbs: format!("{:?}", self) } ),
+            Self::None => Ok( Self::None )
         }
-        
     }
 }
 
@@ -75,8 +87,13 @@ impl<'de> Deserialize<'de> for Wbs {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: Deserializer<'de> {
-        let s: &str = serde::de::Deserialize::deserialize(deserializer)?;
-        Wbs::try_from(s).map_err(D::Error::custom)
+        let s: String = serde::de::Deserialize::deserialize(deserializer)?;
+
+        // cost centers are serialized as just the cost center
+        match s.parse() {
+            Ok(cc) => Ok( Wbs::CostCenter { cc } ),
+            Err(_) => Wbs::try_from(s).map_err(D::Error::custom)
+        }
     }
 }
 
@@ -113,10 +130,10 @@ impl<'de> Deserialize<'de> for Wbs {
 // }
 
 impl TryFrom<&str> for Wbs {
-    type Error = anyhow::Error;
+    type Error = Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value == "" {
+        if value.is_empty() {
             return Ok(Self::None);
         }
 
@@ -144,20 +161,24 @@ impl TryFrom<&str> for Wbs {
         }
 
         else {
-            Err( anyhow!("Failed to parse WBS <{}>", value) )
+            Err( Error::parse("WBS", value) )
         }
     }
 }
 
-impl From<String> for Wbs {
-    fn from(value: String) -> Self {
-        Self::try_from( value.as_str() ).unwrap()
+impl TryFrom<String> for Wbs {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from( value.as_str() )
     }
 }
 
-impl From<regex::Match<'_>> for Wbs {
-    fn from(value: regex::Match) -> Self {
-        Self::try_from( value.as_str() ).unwrap()
+impl TryFrom<regex::Match<'_>> for Wbs {
+    type Error = Error;
+
+    fn try_from(value: regex::Match) -> Result<Self, Self::Error> {
+        Self::try_from( value.as_str() )
     }
 }
 
@@ -165,8 +186,8 @@ impl Display for Wbs {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::CostCenter { cc            } => write!(f, "{}", cc),
-            Self::Hd         { job, id       } => write!(f, "D-{}-{}", job, id),
-            Self::Legacy     { job, shipment } => write!(f, "S-{}-{}", job, shipment),
+            Self::Hd         { job, id       } => write!(f, "D-{}-{:05}", job, id),
+            Self::Legacy     { job, shipment } => write!(f, "S-{}-2-{:02}", job, shipment),
             Self::None                         => write!(f, ""),
         }
     }
@@ -182,3 +203,36 @@ impl Debug for Wbs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(wbs: Wbs, text: &str) {
+        let json = serde_json::to_string(&wbs).unwrap();
+        assert_eq!(json, format!("\"{}\"", text));
+        assert_eq!(serde_json::from_str::<Wbs>(&json).unwrap(), wbs);
+    }
+
+    #[test]
+    fn none_round_trips() {
+        round_trip(Wbs::None, "");
+    }
+
+    #[test]
+    fn cost_center_round_trips() {
+        // displayed as just the cost center, which does not parse as a WBS element
+        round_trip(Wbs::CostCenter { cc: 2005 }, "2005");
+        assert_eq!(Wbs::try_from("S-1200001-2-2005").unwrap(), Wbs::CostCenter { cc: 2005 });
+    }
+
+    #[test]
+    fn hd_round_trips_with_padded_id() {
+        round_trip(Wbs::Hd { job: "1200001".into(), id: 2 }, "D-1200001-00002");
+    }
+
+    #[test]
+    fn legacy_round_trips_with_padded_shipment() {
+        round_trip(Wbs::Legacy { job: "1200001".into(), shipment: 5 }, "S-1200001-2-05");
+    }
+}
@@ -1,38 +1,590 @@
 
-use clap::Parser;
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use serde::Serialize;
 use std::error::Error;
-use sap_watch::db::Sndb;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use sap_watch::api::Area;
+use sap_watch::dates::DateRange;
+use sap_watch::db::{config, BurnFilter, BurnedPart, Sndb};
+use sap_watch::excel::cogi::{parse_cogi_xl, parse_cogi_xl_with_errors};
+use sap_watch::excel::cohv::{parse_cohv_xl, parse_cohv_xl_with_errors};
+use sap_watch::excel::{mb51::parse_mb51_xl, mb52::parse_mb52_xl};
+use sap_watch::notify::webhook::{self, WebhookConfig};
+use sap_watch::notify::{Alert, Digest, Reported, SmtpNotifier, WebhookNotifier};
+use sap_watch::output::{self, Color, Format, Severity, TableOptions, Tabular};
+use sap_watch::recon;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 struct Args {
+    /// output format
+    #[arg(short, long, global = true, value_enum, default_value_t)]
+    format: Format,
+
+    /// when to color table output
+    #[arg(long, global = true, value_enum, default_value_t)]
+    color: Color,
+
+    #[command(flatten)]
+    snapshot: SnapshotArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// list parts burned in Sigmanest
+    Burns {
+        #[command(flatten)]
+        range: DateRange,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+
+        /// MB51 export of postings, to only list burns not yet posted in SAP
+        #[arg(long)]
+        mb51: Option<PathBuf>,
+    },
+
+    /// list programs burned in Sigmanest
+    Programs {
+        #[command(flatten)]
+        range: DateRange,
+
+        /// only list repeats of a program
+        #[arg(long)]
+        program: Option<String>,
+    },
+
+    /// compare SAP open orders in a COHV export against the parts loaded in Sigmanest
+    Demand {
+        /// COHV export file
+        #[arg(required_unless_present = "loaded")]
+        cohv: Option<PathBuf>,
+
+        /// list the parts loaded in Sigmanest instead of comparing
+        #[arg(long, conflicts_with = "cohv")]
+        loaded: bool,
+    },
+
+    /// list programs nested in Sigmanest that have not been burned
+    Nested,
+
+    /// show how the sheets of burned programs were used (consumed, remnants and scrap)
+    Remnants {
+        #[command(flatten)]
+        range: DateRange,
+
+        /// list the remnants instead of the sheet usage
+        #[arg(long, conflicts_with = "by_machine")]
+        list: bool,
+
+        /// total scrap by machine
+        #[arg(long)]
+        by_machine: bool,
+    },
+
+    /// inspect a COHV export
+    Orders {
+        /// COHV export file
+        cohv: PathBuf,
+
+        /// only list orders for a piece mark
+        #[arg(long)]
+        mark: Option<String>,
+    },
+
+    /// reconcile burns against the orders in a COHV export
+    Reconcile {
+        /// COHV export file
+        cohv: PathBuf,
+
+        #[command(flatten)]
+        range: DateRange,
+
+        /// MB51 export of postings, to only reconcile burns not yet posted in SAP
+        #[arg(long)]
+        mb51: Option<PathBuf>,
+
+        /// also write the findings to an excel workbook
+        #[arg(long)]
+        xlsx: Option<PathBuf>,
+    },
+
+    /// compare two reconciliation runs, stored (`reconcile` json output) or reconciled from COHV exports
+    Diff {
+        /// earlier run or COHV export
+        before: PathBuf,
+
+        /// later run or COHV export
+        after: PathBuf,
+
+        /// burns to reconcile COHV exports against
+        #[command(flatten)]
+        range: DateRange,
+    },
+
+    /// compare the orders of two COHV exports
+    OrderDiff {
+        /// earlier COHV export
+        before: PathBuf,
+
+        /// later COHV export
+        after: PathBuf,
+
+        /// check deleted orders for parts burned in the date range
+        #[arg(long)]
+        burns: bool,
+
+        #[command(flatten)]
+        range: DateRange,
+    },
+
+    /// list planned orders with burns that must be converted to production orders
+    Conversions {
+        /// COHV export file
+        cohv: PathBuf,
+
+        /// write the planned order numbers for CO41 here
+        #[arg(long)]
+        co41: Option<PathBuf>,
+    },
+
+    /// email a digest of findings and COGI errors (configured by `SMTP_*` variables)
+    Digest {
+        /// COHV export file
+        cohv: PathBuf,
+
+        /// COGI export file
+        #[arg(long)]
+        cogi: Option<PathBuf>,
+
+        #[command(flatten)]
+        range: DateRange,
+
+        /// file the rows reported are kept in, to only report rows not reported before
+        #[arg(long)]
+        state: Option<PathBuf>,
+
+        /// write the html of the digest instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// post critical findings and COGI errors to the webhook (configured by `WEBHOOK_*` variables)
+    Alert {
+        /// COHV export file
+        cohv: PathBuf,
+
+        /// COGI export file
+        #[arg(long)]
+        cogi: Option<PathBuf>,
+
+        #[command(flatten)]
+        range: DateRange,
+
+        /// write the payload instead of posting it (alerts already sent are included)
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// generate the SAP confirmation upload
+    Confirm {
+        /// write the tab-delimited upload file here
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// classify a COGI export and link the errors to burns
+    Cogi {
+        /// COGI export file
+        cogi: PathBuf,
+
+        #[command(flatten)]
+        range: DateRange,
+    },
+
+    /// check that burns are covered by the stock in a MB52 export
+    Stock {
+        /// MB52 export file
+        mb52: PathBuf,
+
+        #[command(flatten)]
+        range: DateRange,
+    },
+
+    /// compare the Sigmanest inventory against the stock in a MB52 export
+    Inventory {
+        /// MB52 export file, list the Sigmanest inventory if not given
+        mb52: Option<PathBuf>,
+
+        /// area difference (in FT2) allowed before stock is flagged
+        #[arg(long, default_value_t = 1.0)]
+        tolerance: f64,
+    },
+
+    /// check configuration and Sigmanest connectivity
+    CheckConfig,
+}
+
+#[derive(Debug, ClapArgs)]
+struct SnapshotArgs {
+    /// record Sigmanest query results to a snapshot file
+    #[arg(long, global = true, value_name = "SNAPSHOT", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// replay Sigmanest query results from a snapshot file instead of connecting
+    #[arg(long, global = true, value_name = "SNAPSHOT")]
+    replay: Option<PathBuf>,
+}
+
+impl SnapshotArgs {
+    async fn sndb(&self) -> sap_watch::Result<Sndb> {
+        let sn = match &self.replay {
+            Some(path) => Sndb::replay(path)?,
+            None => Sndb::init().await?,
+        };
+
+        Ok( match &self.record {
+            Some(path) => sn.record_to(path),
+            None => sn,
+        } )
+    }
+}
+
+#[derive(Debug, ClapArgs)]
+struct FilterArgs {
+    /// part name
     #[arg(long)]
     part: Option<String>,
 
-    #[arg(short, long)]
-    prev_week: bool,
+    /// program name
+    #[arg(long)]
+    program: Option<String>,
+
+    /// job number
+    #[arg(long)]
+    job: Option<String>,
+
+    /// plant (HS01/HS02)
+    #[arg(long)]
+    plant: Option<String>,
+}
+
+impl From<FilterArgs> for BurnFilter {
+    fn from(value: FilterArgs) -> Self {
+        let FilterArgs { part, program, job, plant } = value;
+
+        Self { part, program, job, plant }
+    }
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: &'static str,
+    result: Result<String, String>,
+}
+
+impl Tabular for Check {
+    fn header() -> Vec<&'static str> {
+        vec!["Check", "Status", "Detail"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        match &self.result {
+            Ok(detail)  => vec![self.name.into(), "ok".into(), detail.clone()],
+            Err(detail) => vec![self.name.into(), "error".into(), detail.clone()],
+        }
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.result {
+            Ok(_)  => Some(Severity::Ok),
+            Err(_) => Some(Severity::Error),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    sap_watch::logging::init_logger();
-    
+    sap_watch::logging::init_logger()?;
+
     let args = Args::parse();
-    let mut sn = Sndb::init().await?;
-    
-    if let Some(part) = args.part {
-        let qty = sn.get_part_burned_qty(&part).await?;
-        println!("{}: {}", part, qty);
-    };
-
-    if args.prev_week {
-        let week = sn.get_parts_burned_for_week().await?;
-        for x in &week {
-            println!("{:?}", x);
-        }
+    let out = io::stdout().lock();
+    let opts = TableOptions::stdout(args.color);
+
+    match args.command {
+        Command::Burns { range, filter, mb51 } => {
+            let sn = args.snapshot.sndb().await?;
+            let burns = range.get_burns(&sn).await?;
+            let burns = match mb51 {
+                Some(mb51) => recon::outstanding_burns(&burns, &parse_mb51_xl(mb51)?),
+                None => burns.iter().collect()
+            };
+
+            let filter = BurnFilter::from(filter);
+            output::write_with(out, args.format, &opts, burns.into_iter().filter(|part| filter.matches(part)))?;
+        },
+
+        Command::Programs { range, program } => {
+            let sn = args.snapshot.sndb().await?;
+            let programs = range.get_programs(&sn).await?;
+            let programs = programs
+                .iter()
+                .filter(|prog| program.as_ref().is_none_or(|name| *name == prog.name));
+
+            output::write_with(out, args.format, &opts, programs)?;
+        },
+
+        Command::Demand { cohv, .. } => {
+            let sn = args.snapshot.sndb().await?;
+            let parts = sn.get_loaded_parts().await?;
+
+            // clap requires one of `cohv` or `loaded`
+            match cohv {
+                Some(cohv) => {
+                    let orders = parse_cohv_xl(cohv)?;
+                    output::write_with(out, args.format, &opts, &recon::compare_demand(&orders, &parts))?
+                },
+                None => output::write_with(out, args.format, &opts, &parts)?,
+            }
+        },
+
+        Command::Nested => {
+            let sn = args.snapshot.sndb().await?;
+            let programs = sn.get_nested_programs().await?;
+
+            output::write_with(out, args.format, &opts, &programs)?;
+        },
+
+        Command::Remnants { range, list, by_machine } => {
+            let sn = args.snapshot.sndb().await?;
+            let (from, to) = range.bounds();
+            let remnants = sn.get_remnants(from, to).await?;
+
+            if list {
+                output::write_with(out, args.format, &opts, &remnants)?;
+            } else {
+                let programs = sn.get_programs(from, to).await?;
+                let burns = sn.get_parts_burned(from, to).await?;
+                let usage = recon::sheet_usage(&programs, &burns, &remnants);
+
+                match by_machine {
+                    true  => output::write_with(out, args.format, &opts, &recon::scrap_by_machine(&usage))?,
+                    false => output::write_with(out, args.format, &opts, &usage)?,
+                }
+            }
+        },
+
+        Command::Orders { cohv, mark } => {
+            let orders = parse_cohv_xl(cohv)?;
+            let orders = orders
+                .iter()
+                .filter(|order| mark.as_ref().is_none_or(|mark| *mark == order.data().mark));
+
+            output::write_with(out, args.format, &opts, orders)?;
+        },
+
+        Command::Reconcile { cohv, range, mb51, xlsx } => {
+            let orders = parse_cohv_xl(cohv)?;
+            let sn = args.snapshot.sndb().await?;
+            let burns = range.get_burns(&sn).await?;
+            let burns: Vec<BurnedPart> = match mb51 {
+                Some(mb51) => {
+                    let docs = parse_mb51_xl(mb51)?;
+                    let outstanding: Vec<bool> = recon::Postings::new(&docs)
+                        .apply(&burns)
+                        .iter()
+                        .map(recon::BurnStatus::is_outstanding)
+                        .collect();
+
+                    burns.into_iter().zip(outstanding).filter_map(|(part, keep)| keep.then_some(part)).collect()
+                },
+                None => burns
+            };
+
+            let findings = recon::reconcile(&burns, &orders);
+            if let Some(path) = xlsx {
+                output::xlsx::write_reconciliation(path, &findings)?;
+            }
+
+            output::write_with(out, args.format, &opts, &findings)?;
+        },
+
+        Command::Diff { before, after, range } => {
+            let is_run = |path: &PathBuf| matches!(path.extension().and_then(|ext| ext.to_str()), Some("json" | "ndjson"));
+            let burns = match is_run(&before) && is_run(&after) {
+                true  => Vec::new(),
+                false => range.get_burns(&args.snapshot.sndb().await?).await?,
+            };
+
+            let findings = |path: PathBuf| match is_run(&path) {
+                true  => recon::load_findings(path),
+                false => parse_cohv_xl(path).map(|orders| recon::reconcile(&burns, &orders)),
+            };
+            let changes = recon::diff_findings(&findings(before)?, &findings(after)?);
+
+            output::write_with(out, args.format, &opts, &changes)?;
+        },
+
+        Command::OrderDiff { before, after, burns, range } => {
+            let after = parse_cohv_xl(after)?;
+            let mut changes = recon::diff_orders(&parse_cohv_xl(before)?, &after);
+            if burns {
+                let sn = args.snapshot.sndb().await?;
+                recon::burned_on_deleted(&mut changes, &range.get_burns(&sn).await?, &after);
+            }
+
+            output::write_with(out, args.format, &opts, &changes)?;
+        },
+
+        Command::Conversions { cohv, co41 } => {
+            let orders = parse_cohv_xl(cohv)?;
+            let sn = args.snapshot.sndb().await?;
+
+            let conversions = recon::conversions(&orders, &sn.get_parts_burned_qty().await?);
+            if let Some(path) = co41 {
+                let mut file = File::create(path)?;
+                for conversion in &conversions {
+                    writeln!(file, "{}", conversion.to_co41_line())?;
+                }
+            }
+
+            output::write_with(out, args.format, &opts, &conversions)?;
+        },
+
+        Command::Digest { cohv, cogi, range, state, dry_run } => {
+            let (orders, mut errors) = parse_cohv_xl_with_errors(cohv)?;
+            let sn = args.snapshot.sndb().await?;
+            let burns = range.get_burns(&sn).await?;
+
+            let (from, to) = range.bounds();
+            let mut digest = Digest::new(format!("sap-watch digest for {} to {}", from, to));
+            digest.findings(&recon::reconcile(&burns, &orders));
+
+            if let Some(cogi) = cogi {
+                let (cogi, cogi_errors) = parse_cogi_xl_with_errors(cogi)?;
+                errors.extend(cogi_errors);
+
+                let causes: Vec<_> = recon::root_causes(cogi, &burns).into_values().flatten().collect();
+                digest.cogi(&causes);
+            }
+            digest.errors(&errors);
+
+            let reported = match &state {
+                Some(path) => Some( digest.skip_reported(&Reported::load(path)?) ),
+                None => None
+            };
+
+            match dry_run {
+                true  => write!(io::stdout(), "{}", digest.to_html())?,
+                false => {
+                    let sent = SmtpNotifier::from_env()?.send(&digest).await?;
+                    writeln!(io::stderr(), "sent {} digest email(s) ({})", sent, digest.summary())?;
+
+                    if let (Some(path), Some(reported)) = (state, reported) {
+                        reported.save(path)?;
+                    }
+                },
+            }
+        },
+
+        Command::Alert { cohv, cogi, range, dry_run } => {
+            let orders = parse_cohv_xl(cohv)?;
+            let sn = args.snapshot.sndb().await?;
+            let burns = range.get_burns(&sn).await?;
+
+            let mut alerts = Alert::critical("Findings", &recon::reconcile(&burns, &orders));
+            if let Some(cogi) = cogi {
+                let causes: Vec<_> = recon::root_causes(parse_cogi_xl(cogi)?, &burns).into_values().flatten().collect();
+                alerts.extend(Alert::critical("COGI risks", &causes));
+            }
+
+            let (from, to) = range.bounds();
+            let title = format!("sap-watch: critical findings for {} to {}", from, to);
+            match dry_run {
+                true  => {
+                    let template = WebhookConfig::template_from_env()?;
+                    writeln!(io::stdout(), "{}", webhook::payload(template.as_deref(), &title, &alerts.iter().collect::<Vec<_>>())?)?
+                },
+                false => {
+                    let sent = WebhookNotifier::from_env()?.send(&title, &alerts).await?;
+                    writeln!(io::stderr(), "posted {} of {} alert(s)", sent, alerts.len())?;
+                },
+            }
+        },
+
+        Command::Confirm { output } => {
+            let sn = args.snapshot.sndb().await?;
+            let confirmations = sn.get_confirmations().await?;
+
+            if let Some(path) = output {
+                let mut file = File::create(path)?;
+                for cnf in &confirmations {
+                    writeln!(file, "{}", cnf.to_upload_line())?;
+                }
+            }
+
+            output::write_with(out, args.format, &opts, &confirmations)?;
+        },
+
+        Command::Cogi { cogi, range } => {
+            let errors = parse_cogi_xl(cogi)?;
+            let sn = args.snapshot.sndb().await?;
+            let burns = range.get_burns(&sn).await?;
+
+            let report = recon::root_causes(errors, &burns);
+            output::write_with(out, args.format, &opts, report.values().flatten())?;
+        },
+
+        Command::Stock { mb52, range } => {
+            let stock = parse_mb52_xl(mb52)?;
+            let sn = args.snapshot.sndb().await?;
+            let burns = range.get_burns(&sn).await?;
+
+            output::write_with(out, args.format, &opts, &recon::check_stock(&burns, &stock))?;
+        },
+
+        Command::Inventory { mb52, tolerance } => {
+            let sn = args.snapshot.sndb().await?;
+            let sheets = sn.get_stock().await?;
+
+            match mb52 {
+                Some(mb52) => {
+                    let stock = parse_mb52_xl(mb52)?;
+                    let diffs = recon::compare_stock(&sheets, &stock, Area::ft2(tolerance));
+                    output::write_with(out, args.format, &opts, &diffs)?
+                },
+                None => output::write_with(out, args.format, &opts, &sheets)?,
+            }
+        },
+
+        Command::CheckConfig => {
+            let mut checks = vec![Check {
+                name: "Sigmanest config",
+                result: config::from_env()
+                    .map(|config| format!("server {}", config.get_addr()))
+                    .map_err(|e| e.to_string())
+            }];
+
+            if let Ok(config) = config::from_env() {
+                checks.push(Check {
+                    name: "Sigmanest connection",
+                    result: Sndb::connect(config).await
+                        .map(|_| "connected".into())
+                        .map_err(|e| e.to_string())
+                });
+            }
 
-        let progs: std::collections::HashSet<&String> = week.iter().map(|x| &x.program).collect();
-        println!("Programs: {}", progs.len());
+            output::write_with(out, args.format, &opts, &checks)?;
+            if checks.iter().any(|check| check.result.is_err()) {
+                return Err("configuration check failed".into());
+            }
+        },
     }
 
     Ok(())
@@ -0,0 +1,176 @@
+
+use clap::{ArgGroup, Parser};
+use ftlog::{error, info};
+use itertools::Itertools;
+use std::error::Error;
+use std::io;
+#[cfg(feature = "http")]
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use sap_watch::dates;
+use sap_watch::db::{BurnedPart, HighWaterMark, Since, Sndb};
+use sap_watch::excel::{cogi::parse_cogi_xl, cohv::parse_cohv_xl};
+use sap_watch::notify::{Alert, WebhookNotifier};
+use sap_watch::output::{self, Format};
+use sap_watch::recon;
+#[cfg(feature = "http")]
+use sap_watch::server::{self, ApiState};
+
+/// watch Sigmanest for new burns
+#[derive(Debug, Parser)]
+#[command(author, version, about, group(ArgGroup::new("exports").multiple(true)))]
+struct Args {
+    /// output format for new burns
+    #[arg(short, long, value_enum, default_value_t = Format::Ndjson)]
+    format: Format,
+
+    /// file the last processed archive position is kept in
+    #[arg(long, default_value = "watch-state.json")]
+    state: PathBuf,
+
+    /// seconds between polls
+    #[arg(long, default_value_t = 60)]
+    interval: u64,
+
+    /// poll once and exit
+    #[arg(long)]
+    once: bool,
+
+    /// post critical findings in new burns to the webhook (configured by
+    /// `WEBHOOK_*` variables), checked against `--cohv` and `--cogi`
+    #[arg(long, requires = "exports")]
+    alert: bool,
+
+    /// COHV export to reconcile new burns against (and serve orders from)
+    #[arg(long, group = "exports")]
+    cohv: Option<PathBuf>,
+
+    /// COGI export to check new burns against
+    #[arg(long, group = "exports")]
+    cogi: Option<PathBuf>,
+
+    /// serve the http api on this address (e.g. `127.0.0.1:8080`)
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    listen: Option<SocketAddr>,
+}
+
+/// posts the critical findings for new burns to the webhook
+///
+/// The exports are read on each poll, so they can be refreshed while watching.
+/// Short orders are checked against the quantity burned to date of each mark
+/// in the new burns, so orders burned over across several polls are found.
+async fn alert(sn: &Sndb, webhook: &mut WebhookNotifier, burns: &[BurnedPart], args: &Args) -> sap_watch::Result<()> {
+    let mut alerts = Vec::new();
+    if let Some(cohv) = &args.cohv {
+        let orders = parse_cohv_xl(cohv.clone())?;
+
+        let mut burned = Vec::new();
+        for mark in burns.iter().map(|part| part.part.as_str()).unique() {
+            burned.extend(sn.get_part_burned_by_plant(mark).await?);
+        }
+
+        alerts.extend(Alert::critical("Findings", &recon::reconcile_to_date(burns, &burned, &orders)));
+    }
+    if let Some(cogi) = &args.cogi {
+        let causes: Vec<_> = recon::root_causes(parse_cogi_xl(cogi.clone())?, burns)
+            .into_values()
+            .flatten()
+            .filter(|cause| !cause.parts.is_empty())
+            .collect();
+        alerts.extend(Alert::critical("COGI risks", &causes));
+    }
+
+    let sent = webhook.send("sap-watch: critical findings in new burns", &alerts).await?;
+    if sent > 0 {
+        info!("posted {} alert(s) to the webhook", sent);
+    }
+
+    Ok(())
+}
+
+async fn poll(sn: &Sndb, mark: &mut HighWaterMark, webhook: Option<&mut WebhookNotifier>, args: &Args) -> sap_watch::Result<()> {
+    // with no saved mark, start from the beginning of today
+    let since = mark.since().unwrap_or_else(|| {
+        let today = dates::today();
+        Since::Time(today.midnight())
+    });
+
+    let burns = sn.get_parts_burned_since(since).await?;
+    if burns.is_empty() {
+        return Ok(());
+    }
+
+    info!("{} new parts burned since {:?}", burns.len(), since);
+    output::write(io::stdout().lock(), args.format, &burns)
+        .map_err(sap_watch::Error::io("writing new burns"))?;
+
+    // the burns are already written, so a failed alert does not hold up the mark
+    if let Some(webhook) = webhook {
+        if let Err(e) = alert(sn, webhook, &burns, args).await {
+            error!("failed to alert on new burns: {}", e);
+        }
+    }
+
+    // only save the mark once the burns are processed so none are missed on restart
+    mark.advance(&burns);
+    mark.save(&args.state)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    sap_watch::logging::init_logger()?;
+
+    let args = Args::parse();
+    let mut mark = HighWaterMark::load(&args.state)?;
+    let sn = Sndb::init().await?;
+    let mut webhook = match args.alert {
+        true  => Some( WebhookNotifier::from_env()? ),
+        false => None,
+    };
+
+    #[cfg(feature = "http")]
+    let health = match args.listen {
+        Some(addr) => {
+            let state = ApiState::new(sn.clone(), args.cohv.clone());
+            let health = state.health();
+
+            let listener = server::listen(addr).await?;
+            tokio::spawn(async move {
+                if let Err(e) = server::serve(listener, state).await {
+                    error!("http api stopped: {}", e);
+                }
+            });
+
+            Some(health)
+        },
+        None => None,
+    };
+
+    loop {
+        let result = poll(&sn, &mut mark, webhook.as_mut(), &args).await;
+
+        #[cfg(feature = "http")]
+        if let Some(health) = &health {
+            health.write().unwrap_or_else(|e| e.into_inner()).polled(&result, &mark);
+        }
+
+        if let Err(e) = result {
+            if args.once {
+                return Err(e.into());
+            }
+
+            error!("failed to poll for new burns: {}", e);
+        }
+
+        if args.once {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! dates of burns, in the local time zone of the plants
+
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::db::{BurnedPart, Program, Sndb};
+use crate::Result;
+
+/// today in the local time zone (UTC if the local offset cannot be determined)
+pub fn today() -> Date {
+    OffsetDateTime::now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .date()
+}
+
+/// The days to query burns for (`--from/--to` in the recon binary, `?from=&to=` in the HTTP API)
+#[derive(Debug, Default, Clone, Deserialize, clap::Args)]
+pub struct DateRange {
+    /// first day of burns (defaults to the start of the previous week)
+    #[arg(long, value_parser = parse_date)]
+    pub from: Option<Date>,
+
+    /// day after the last day of burns (defaults to the start of this week)
+    #[arg(long, value_parser = parse_date)]
+    pub to: Option<Date>,
+}
+
+impl DateRange {
+    /// first day and the day after the last day,
+    /// defaulting to the previous week (weeks start on Sunday)
+    pub fn bounds(&self) -> (Date, Date) {
+        let today = today();
+        let this_week = today - Duration::days(today.weekday().number_days_from_sunday() as i64);
+
+        let to = self.to.unwrap_or(this_week);
+        let from = self.from.unwrap_or(to - Duration::weeks(1));
+
+        (from, to)
+    }
+
+    /// parts burned in the range
+    pub async fn get_burns(&self, sn: &Sndb) -> Result<Vec<BurnedPart>> {
+        let (from, to) = self.bounds();
+
+        sn.get_parts_burned(from, to).await
+    }
+
+    /// programs burned in the range
+    pub async fn get_programs(&self, sn: &Sndb) -> Result<Vec<Program>> {
+        let (from, to) = self.bounds();
+
+        sn.get_programs(from, to).await
+    }
+}
+
+/// parses a `YYYY-MM-DD` date
+pub fn parse_date(value: &str) -> std::result::Result<Date, String> {
+    let err = || format!("invalid date `{}`, expected YYYY-MM-DD", value);
+
+    let parts: Vec<&str> = value.split('-').collect();
+    let [year, month, day] = parts[..] else {
+        return Err(err());
+    };
+
+    let month = month.parse::<u8>().ok().and_then(|m| m.try_into().ok()).ok_or_else(err)?;
+    Date::from_calendar_date(
+        year.parse().map_err(|_| err())?,
+        month,
+        day.parse().map_err(|_| err())?,
+    ).map_err(|_| err())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Month, Weekday};
+
+    use super::*;
+
+    #[test]
+    fn default_range_is_the_previous_week() {
+        let (from, to) = DateRange::default().bounds();
+
+        assert_eq!(from.weekday(), Weekday::Sunday);
+        assert_eq!(to - from, Duration::weeks(1));
+        assert!(to <= today() && today() - to < Duration::weeks(1));
+    }
+
+    #[test]
+    fn explicit_bounds_are_kept() {
+        let from = Date::from_calendar_date(2026, Month::October, 5).unwrap();
+        let to = Date::from_calendar_date(2026, Month::October, 7).unwrap();
+
+        assert_eq!(DateRange { from: Some(from), to: Some(to) }.bounds(), (from, to));
+        assert_eq!(DateRange { from: None, to: Some(to) }.bounds(), (to - Duration::weeks(1), to));
+    }
+
+    #[test]
+    fn dates_are_parsed() {
+        assert_eq!(parse_date("2026-10-05"), Ok(Date::from_calendar_date(2026, Month::October, 5).unwrap()));
+        assert!(parse_date("2026-13-05").is_err());
+        assert!(parse_date("10/05/2026").is_err());
+    }
+}
@@ -1,32 +1,82 @@
 
+use time::PrimitiveDateTime;
+
+use super::query::{columns, FromRow, SqlRow};
+use crate::api::{Area, Qty, Wbs};
+
 /// represents the sql data for a part that was burned (PartArchive table)
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct BurnedPart {
     /// The name of the part
     pub part: String,
     /// Quantity burned
-    pub qty: i32,
+    pub qty: Qty,
     /// the material that the part(s) was burned from
     pub matl: MaterialData,
     /// the name of the program burned
-    pub program: String
+    pub program: String,
+    /// the repeat of the program burned
+    pub repeat_id: i32,
+    /// the archive packet the part was archived in
+    pub packet_id: i32,
+    /// the machine the program was burned on
+    pub machine: String,
+    /// the name of the sheet burned
+    pub sheet: String,
+    /// when the program was archived (burned)
+    pub archived: Option<PrimitiveDateTime>,
 }
 
-impl TryFrom<&tiberius::Row> for BurnedPart {
-    type Error = tiberius::error::Error;
+impl FromRow for BurnedPart {
+    const COLUMNS: &'static [&'static str] = &columns::<{ 8 + MaterialData::COLUMNS.len() }>(
+        &["Part", "Qty", "Program", "RepeatID", "ArchivePacketID", "Machine", "Sheet", "ArcDateTime"],
+        MaterialData::COLUMNS,
+    );
 
-    fn try_from(row: &tiberius::Row) -> Result<Self, Self::Error> {
-        let part = row.get::<&str, _>("Part").unwrap_or_default().into();
-        let qty = row.get::<i32, _>("Qty").unwrap_or_default();
-        let matl = MaterialData::try_from(row)?;
-        let program = row.get::<&str, _>("Program").unwrap_or_default().into();
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let part = row.require::<String>("Part")?;
+        let qty = Qty::try_from( row.require::<i32>("Qty")? )?;
+        let matl = MaterialData::from_row(row)?;
+        let program = row.require::<String>("Program")?;
+        let repeat_id = row.require::<i32>("RepeatID")?;
+        let packet_id = row.require::<i32>("ArchivePacketID")?;
+        let machine = row.require::<String>("Machine")?;
+        let sheet = row.require::<String>("Sheet")?;
+        let archived = row.get::<PrimitiveDateTime>("ArcDateTime")?;
         
-        Ok(Self { part, qty, matl, program })
+        Ok(Self { part, qty, matl, program, repeat_id, packet_id, machine, sheet, archived })
+    }
+}
+
+/// represents the total quantity of a part burned to date (PartArchive table),
+/// by plant and the WBS element of the material burned
+#[derive(Debug, Serialize)]
+pub struct PartBurnedQty {
+    /// The name of the part
+    pub part: String,
+    /// the plant the part was burned at
+    pub plant: String,
+    /// the WBS element of the material burned (if non-stock)
+    pub wbs: Option<String>,
+    /// Quantity burned
+    pub qty: Qty,
+}
+
+impl FromRow for PartBurnedQty {
+    const COLUMNS: &'static [&'static str] = &["Part", "Plant", "Wbs", "Qty"];
+
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let part = row.require::<String>("Part")?;
+        let plant = row.require::<String>("Plant")?;
+        let wbs = row.get::<String>("Wbs")?;
+        let qty = Qty::try_from( row.require::<i32>("Qty")? )?;
+
+        Ok(Self { part, plant, wbs, qty })
     }
 }
 
 /// represents the material data (Stock/StockArchive table)
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct MaterialData {
     /// the name of the material
     pub matl: String,
@@ -37,19 +87,300 @@ pub struct MaterialData {
     /// the plant the material is at
     pub plant: String,          // TODO: Plant struct
     /// the area of the material in question
-    pub area: f64,
+    pub area: Area,
 }
 
-impl TryFrom<&tiberius::Row> for MaterialData {
-    type Error = tiberius::error::Error;
+impl FromRow for MaterialData {
+    const COLUMNS: &'static [&'static str] = &["MaterialMaster", "Wbs", "Location", "Plant", "Area"];
 
-    fn try_from(row: &tiberius::Row) -> Result<Self, Self::Error> {
-        let matl = row.get::<&str, _>("MaterialMaster").unwrap_or_default().into();
-        let wbs = row.get::<&str, _>("Wbs").map(Into::into);
-        let loc = row.get::<&str, _>("Location").unwrap_or_default().into();
-        let plant = row.get::<&str, _>("Plant").unwrap_or_default().into();
-        let area = row.get::<f64, _>("Area").unwrap_or_default();
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let matl = row.require::<String>("MaterialMaster")?;
+        let wbs = row.get::<String>("Wbs")?;
+        // sheets are not always given a location in Sigmanest
+        let loc = row.get::<String>("Location")?.unwrap_or_default();
+        let plant = row.require::<String>("Plant")?;
+        let area = Area::in2( row.require::<f64>("Area")? );
 
         Ok(Self { matl, wbs, loc, plant, area })
     }
 }
+
+/// represents a nest program that was burned (ProgArchive table)
+/// 
+/// Each repeat of a program is a separate sheet burned.
+#[derive(Debug, Serialize)]
+pub struct Program {
+    /// the name of the program
+    pub name: String,
+    /// the repeat of the program
+    pub repeat_id: i32,
+    /// the machine the program was burned on
+    pub machine: String,
+    /// the name of the sheet burned
+    pub sheet: String,
+    /// the sheet material (area is the area of the sheet)
+    pub matl: MaterialData,
+    /// total number of parts on the program
+    pub parts: Qty,
+    /// total nested area of the parts
+    pub nested_area: Area,
+    /// when the program was archived (burned), `None` if it is only nested
+    pub archived: Option<PrimitiveDateTime>,
+}
+
+impl Program {
+    /// fraction of the sheet area used by nested parts
+    pub fn utilization(&self) -> f64 {
+        match self.matl.area.as_in2() {
+            area if area > 0.0 => self.nested_area.as_in2() / area,
+            _ => 0.0
+        }
+    }
+    /// the burned parts that were nested on this program
+    pub fn parts_burned<'a>(&'a self, burns: &'a [BurnedPart]) -> impl Iterator<Item = &'a BurnedPart> {
+        burns.iter().filter(move |part| part.program == self.name && part.repeat_id == self.repeat_id)
+    }
+}
+
+impl FromRow for Program {
+    const COLUMNS: &'static [&'static str] = &columns::<{ 7 + MaterialData::COLUMNS.len() }>(
+        &["Program", "RepeatID", "Machine", "Sheet", "Qty", "NestedArea", "ArcDateTime"],
+        MaterialData::COLUMNS,
+    );
+
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let name = row.require::<String>("Program")?;
+        let repeat_id = row.require::<i32>("RepeatID")?;
+        let machine = row.require::<String>("Machine")?;
+        let sheet = row.require::<String>("Sheet")?;
+        let matl = MaterialData::from_row(row)?;
+        let parts = Qty::try_from( row.require::<i32>("Qty")? )?;
+        let nested_area = Area::in2( row.require::<f64>("NestedArea")? );
+        let archived = row.get::<PrimitiveDateTime>("ArcDateTime")?;
+
+        Ok(Self { name, repeat_id, machine, sheet, matl, parts, nested_area, archived })
+    }
+}
+
+/// represents a remnant cut from a burned sheet (StockArchive table)
+#[derive(Debug, Serialize)]
+pub struct Remnant {
+    /// the name of the remnant sheet
+    pub name: String,
+    /// the program the remnant was cut on
+    pub program: String,
+    /// the repeat of the program the remnant was cut on
+    pub repeat_id: i32,
+    /// the remnant material (area is the area of the remnant)
+    pub matl: MaterialData,
+}
+
+impl FromRow for Remnant {
+    const COLUMNS: &'static [&'static str] = &columns::<{ 3 + MaterialData::COLUMNS.len() }>(
+        &["Remnant", "Program", "RepeatID"],
+        MaterialData::COLUMNS,
+    );
+
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let name = row.require::<String>("Remnant")?;
+        let program = row.require::<String>("Program")?;
+        let repeat_id = row.require::<i32>("RepeatID")?;
+        let matl = MaterialData::from_row(row)?;
+
+        Ok(Self { name, program, repeat_id, matl })
+    }
+}
+
+/// represents a sheet in the Sigmanest inventory (Stock table)
+#[derive(Debug, Serialize)]
+pub struct SheetStock {
+    /// the name of the sheet
+    pub sheet: String,
+    /// the sheet material (area is the total area of all the sheets)
+    pub matl: MaterialData,
+    /// length of the sheet
+    pub length: f64,
+    /// width of the sheet
+    pub width: f64,
+    /// number of sheets
+    pub qty: Qty,
+    /// if the sheet is a remnant of a burned sheet
+    pub remnant: bool,
+}
+
+impl FromRow for SheetStock {
+    const COLUMNS: &'static [&'static str] = &columns::<{ 5 + MaterialData::COLUMNS.len() }>(
+        &["Sheet", "Length", "Width", "Qty", "Remnant"],
+        MaterialData::COLUMNS,
+    );
+
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let sheet = row.require::<String>("Sheet")?;
+        let matl = MaterialData::from_row(row)?;
+        let length = row.require::<f64>("Length")?;
+        let width = row.require::<f64>("Width")?;
+        let qty = Qty::try_from( row.require::<i32>("Qty")? )?;
+        let remnant = row.require::<bool>("Remnant")?;
+
+        Ok(Self { sheet, matl, length, width, qty, remnant })
+    }
+}
+
+/// represents a part loaded into Sigmanest to be nested (Part and PIP tables)
+#[derive(Debug, Serialize)]
+pub struct LoadedPart {
+    /// The name of the part
+    pub part: String,
+    /// the work order the part was loaded on
+    pub wo_number: String,
+    /// quantity required on the work order
+    pub qty_required: Qty,
+    /// quantity currently nested on programs that have not been burned
+    pub qty_nested: Qty,
+    /// quantity already burned
+    pub qty_burned: Qty,
+    /// job and structure (`Data1`, i.e. `1200123A`)
+    pub data1: String,
+    /// shipment (`Data2`)
+    pub data2: String,
+}
+
+impl LoadedPart {
+    /// the job number the part was loaded for
+    pub fn job(&self) -> Option<&str> {
+        // Data1 is the job number followed by the structure letter
+        let mut chars = self.data1.char_indices();
+        chars.next_back().map(|(i, _)| &self.data1[..i]).filter(|job| !job.is_empty())
+    }
+
+    /// the legacy WBS element the part was loaded for
+    pub fn wbs(&self) -> Option<Wbs> {
+        let shipment = self.data2.trim().parse::<u32>().ok()?;
+
+        Wbs::try_from(format!("S-{}-2-{:02}", self.job()?, shipment)).ok()
+    }
+
+    /// quantity still to be burned
+    pub fn qty_remaining(&self) -> Qty {
+        self.qty_required.checked_sub(self.qty_burned).unwrap_or_default()
+    }
+}
+
+impl FromRow for LoadedPart {
+    const COLUMNS: &'static [&'static str] = &[
+        "Part", "WONumber", "QtyRequired", "QtyNested", "QtyBurned", "Data1", "Data2",
+    ];
+
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let part = row.require::<String>("Part")?;
+        let wo_number = row.require::<String>("WONumber")?;
+        let qty_required = Qty::try_from( row.require::<i32>("QtyRequired")? )?;
+        let qty_nested = Qty::try_from( row.require::<i32>("QtyNested")? )?;
+        let qty_burned = Qty::try_from( row.require::<i32>("QtyBurned")? )?;
+        // job and shipment are free text, and left empty on some work orders
+        let data1 = row.get::<String>("Data1")?.unwrap_or_default();
+        let data2 = row.get::<String>("Data2")?.unwrap_or_default();
+
+        Ok(Self { part, wo_number, qty_required, qty_nested, qty_burned, data1, data2 })
+    }
+}
+
+/// represents a line of the SAP confirmation upload (see `sql/sap_cnf_swaldon.sql`)
+#[derive(Debug, Serialize)]
+pub struct Confirmation {
+    /// The name of the part
+    pub part: String,
+    /// job number of the part
+    pub job: String,
+    /// shipment of the part
+    pub shipment: String,
+    /// storage location the part is received into
+    pub storage_location: String,
+    /// Quantity burned
+    pub qty: Qty,
+    /// the material that the part(s) was burned from
+    pub matl: String,
+    /// the WBS element of the material (if non-stock)
+    pub wbs: Option<String>,
+    /// the area of material consumed
+    pub area: Area,
+    /// the location the material is in
+    pub loc: String,
+    /// the plant the material is at
+    pub plant: String,
+    /// the name of the program burned
+    pub program: String
+}
+
+impl Confirmation {
+    /// the confirmation as a tab-delimited line of the SAP upload file
+    pub fn to_upload_line(&self) -> String {
+        [
+            self.part.as_str(),
+            self.job.as_str(),
+            self.shipment.as_str(),
+            self.storage_location.as_str(),
+            &self.qty.0.to_string(),
+            "EA",
+            self.matl.as_str(),
+            self.wbs.as_deref().unwrap_or_default(),
+            &format!("{:.3}", self.area.as_in2()),
+            "IN2",
+            self.loc.as_str(),
+            self.plant.as_str(),
+            self.program.as_str(),
+        ].join("\t")
+    }
+}
+
+impl FromRow for Confirmation {
+    const COLUMNS: &'static [&'static str] = &[
+        "PartName", "Job", "Shipment", "StorageLocation", "QtyProgram", "PrimeCode", "WBS_C",
+        "RectArea", "Location", "Plant", "ProgramName",
+    ];
+
+    fn from_row(row: &SqlRow) -> crate::Result<Self> {
+        let part = row.require::<String>("PartName")?;
+        // job and shipment are built from the part's free text Data1 and Data2
+        let job = row.get::<String>("Job")?.unwrap_or_default();
+        let shipment = row.get::<String>("Shipment")?.unwrap_or_default();
+        let storage_location = row.require::<String>("StorageLocation")?;
+        let qty = Qty::try_from( row.require::<i32>("QtyProgram")? )?;
+        let matl = row.require::<String>("PrimeCode")?;
+        let wbs = row.get::<String>("WBS_C")?.filter(|wbs| !wbs.is_empty());
+        let area = Area::in2( row.require::<f64>("RectArea")? );
+        let loc = row.get::<String>("Location")?.unwrap_or_default();
+        let plant = row.require::<String>("Plant")?;
+        let program = row.require::<String>("ProgramName")?;
+
+        Ok(Self { part, job, shipment, storage_location, qty, matl, wbs, area, loc, plant, program })
+    }
+}
+
+/// filters for burned parts
+#[derive(Debug, Default, Deserialize)]
+pub struct BurnFilter {
+    /// part name
+    pub part: Option<String>,
+    /// program name
+    pub program: Option<String>,
+    /// job number of the material WBS element
+    pub job: Option<String>,
+    /// plant
+    pub plant: Option<String>,
+}
+
+impl BurnFilter {
+    /// if the burned part passes all the filters that are set
+    pub fn matches(&self, part: &BurnedPart) -> bool {
+        let job = || part.matl.wbs
+            .as_deref()
+            .and_then(|wbs| Wbs::try_from(wbs).ok())
+            .and_then(|wbs| wbs.job().map(String::from));
+
+        self.part.as_ref().is_none_or(|p| *p == part.part)
+            && self.program.as_ref().is_none_or(|p| *p == part.program)
+            && self.plant.as_ref().is_none_or(|p| *p == part.matl.plant)
+            && self.job.as_ref().is_none_or(|j| Some(j) == job().as_ref())
+    }
+}
@@ -0,0 +1,84 @@
+
+//! Sigmanest connection configuration
+
+use std::env;
+use std::path::Path;
+
+use tiberius::{AuthMethod, Config};
+
+use crate::{Error, Result};
+
+const DEFAULT_HOST: &str = "hiiwinbl18";
+const DEFAULT_DATABASE: &str = "SNDBase91";
+
+/// reads an optional environment variable, treating empty as unset
+pub(crate) fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|val| !val.is_empty())
+}
+
+/// reads an environment variable that must be set
+pub(crate) fn required(name: &str) -> Result<String> {
+    var(name).ok_or_else(|| Error::Config(format!("environment variable `{}` not defined", name)))
+}
+
+/// builds the Sigmanest connection config from the environment
+///
+/// | variable                 | use                                                    |
+/// |--------------------------|--------------------------------------------------------|
+/// | `SNDB_CONNECTION_STRING` | ADO.NET connection string, used instead of the below   |
+/// | `SNDB_HOST`              | server host (default `hiiwinbl18`)                     |
+/// | `SNDB_PORT`              | server port                                            |
+/// | `SNDB_INSTANCE`          | named instance, resolved through SQL Browser           |
+/// | `SNDB_DATABASE`          | database (default `SNDBase91`)                         |
+/// | `SNDB_AUTH`              | `sql` (default) or `integrated` (Kerberos/Windows)     |
+/// | `SNDB_USER`/`SNDB_PWD`   | login for `sql` auth                                   |
+/// | `SNDB_CA_FILE`           | CA certificate to validate the server certificate with |
+/// | `SNDB_TRUST_CERT`        | set to `true` to skip certificate validation           |
+pub fn from_env() -> Result<Config> {
+    if let Some(conn_str) = var("SNDB_CONNECTION_STRING") {
+        return Config::from_ado_string(&conn_str)
+            .map_err(|e| Error::Config(format!("invalid `SNDB_CONNECTION_STRING`: {}", e)));
+    }
+
+    let mut config = Config::new();
+    config.host(var("SNDB_HOST").as_deref().unwrap_or(DEFAULT_HOST));
+    config.database(var("SNDB_DATABASE").as_deref().unwrap_or(DEFAULT_DATABASE));
+
+    if let Some(port) = var("SNDB_PORT") {
+        let port = port.parse().map_err(|_| Error::Config(format!("invalid `SNDB_PORT` <{}>", port)))?;
+        config.port(port);
+    }
+    if let Some(instance) = var("SNDB_INSTANCE") {
+        config.instance_name(instance);
+    }
+
+    config.authentication(auth()?);
+
+    match (var("SNDB_CA_FILE"), var("SNDB_TRUST_CERT")) {
+        (Some(ca_file), _) => {
+            if !Path::new(&ca_file).is_file() {
+                return Err( Error::Config(format!("CA file `{}` does not exist", ca_file)) );
+            }
+
+            config.trust_cert_ca(ca_file);
+        },
+        (None, Some(trust)) if trust.eq_ignore_ascii_case("true") => config.trust_cert(),
+        // validate against the system root certificates
+        _ => ()
+    }
+
+    Ok(config)
+}
+
+fn auth() -> Result<AuthMethod> {
+    match var("SNDB_AUTH").as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("sql") => Ok( AuthMethod::sql_server(required("SNDB_USER")?, required("SNDB_PWD")?) ),
+
+        #[cfg(feature = "integrated-auth")]
+        Some("integrated") => Ok( AuthMethod::Integrated ),
+        #[cfg(not(feature = "integrated-auth"))]
+        Some("integrated") => Err( Error::Config("built without the `integrated-auth` feature".into()) ),
+
+        Some(other) => Err( Error::Config(format!("unknown `SNDB_AUTH` <{}>, expected `sql` or `integrated`", other)) ),
+    }
+}
@@ -0,0 +1,90 @@
+
+//! tracking how far the Sigmanest archive has been processed
+
+use std::path::Path;
+
+use time::{Date, Month, PrimitiveDateTime};
+
+use super::BurnedPart;
+use crate::{state, Result};
+
+/// A position in the Sigmanest archive to fetch burns after
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Since {
+    /// burns archived in a packet after this ArchivePacketID
+    Packet(i32),
+    /// burns archived at or after this time
+    Time(PrimitiveDateTime),
+}
+
+impl Since {
+    /// the (ArchivePacketID, ArcDateTime) to query burns after
+    ///
+    /// The query takes `ArchivePacketID > @packet_id AND ArcDateTime >= @archived`,
+    /// so burns sharing the time of a [`Since::Time`] are not missed.
+    pub(crate) fn params(&self) -> (i32, PrimitiveDateTime) {
+        // earliest date a SQL Server datetime can hold
+        let min = Date::from_calendar_date(1753, Month::January, 1)
+            .expect("valid date")
+            .midnight();
+
+        match self {
+            Self::Packet(id) => (*id, min),
+            Self::Time(time) => (0, *time),
+        }
+    }
+}
+
+/// The last position in the Sigmanest archive that was processed
+///
+/// Persisted between runs so that a restart resumes where it left off.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighWaterMark {
+    /// last ArchivePacketID processed
+    pub packet_id: Option<i32>,
+    /// archive time of the last burn processed
+    pub archived: Option<PrimitiveDateTime>,
+}
+
+impl HighWaterMark {
+    /// loads the mark from `path`, or an empty mark if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        state::load_or_default(path.as_ref(), "high-water mark")
+    }
+
+    /// saves the mark to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        state::save(self, path.as_ref(), "high-water mark")
+    }
+
+    /// where to fetch burns after, preferring the packet id since it is exact
+    pub fn since(&self) -> Option<Since> {
+        match (self.packet_id, self.archived) {
+            (Some(id), _)   => Some(Since::Packet(id)),
+            (None, Some(t)) => Some(Since::Time(t)),
+            (None, None)    => None,
+        }
+    }
+
+    /// moves the mark past the given burns
+    pub fn advance(&mut self, burns: &[BurnedPart]) {
+        for part in burns {
+            self.packet_id = self.packet_id.max(Some(part.packet_id));
+            self.archived = self.archived.max(part.archived);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_mode_does_not_filter_by_packet() {
+        let time = Date::from_calendar_date(2026, Month::October, 19).unwrap().midnight();
+
+        assert_eq!(Since::Time(time).params(), (0, time));
+        assert_eq!(Since::Packet(42).params().0, 42);
+        assert!(Since::Packet(42).params().1 < time);
+    }
+}
@@ -2,7 +2,16 @@
 //! database abstractions
 
 mod api;
-pub use api::{BurnedPart, MaterialData};
+pub use api::{BurnFilter, BurnedPart, Confirmation, LoadedPart, MaterialData, PartBurnedQty, Program, Remnant, SheetStock};
+
+pub mod config;
+
+mod mark;
+pub use mark::{HighWaterMark, Since};
+
+mod pool;
+pub mod snapshot;
+pub mod query;
 
 mod sn;
 pub use sn::Sndb;
@@ -0,0 +1,60 @@
+
+//! pooled database connections
+
+use ftlog::trace;
+use tiberius::{Client, Config, SqlBrowser};
+use tokio::net::TcpStream;
+use tokio_util::compat::TokioAsyncWriteCompatExt;
+
+use super::MssqlClient;
+
+/// A pooled connection
+///
+/// `broken` is set while a query is running and cleared when it completes,
+/// so a connection whose query was cancelled (i.e. timed out) or failed
+/// mid-stream is dropped by the pool instead of being reused.
+pub(crate) struct Connection {
+    pub(crate) client: MssqlClient,
+    pub(crate) broken: bool,
+}
+
+/// [`bb8`] connection manager for SQL Server
+#[derive(Debug)]
+pub(crate) struct MssqlManager {
+    config: Config,
+}
+
+impl MssqlManager {
+    pub(crate) fn new(config: Config) -> Self {
+        Self { config }
+    }
+}
+
+impl bb8::ManageConnection for MssqlManager {
+    type Connection = Connection;
+    type Error = tiberius::error::Error;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        // resolves named instances through SQL Browser, otherwise connects directly
+        trace!("opening TCP stream");
+        let tcp = TcpStream::connect_named(&self.config).await?;
+        tcp.set_nodelay(true)?;
+
+        // To be able to use Tokio's tcp, we're using the `compat_write` from
+        // the `TokioAsyncWriteCompatExt` to get a stream compatible with the
+        // traits from the `futures` crate.
+        let client = Client::connect(self.config.clone(), tcp.compat_write()).await?;
+
+        Ok( Connection { client, broken: false } )
+    }
+
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        conn.client.simple_query("SELECT 1").await?.into_row().await?;
+
+        Ok(())
+    }
+
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        conn.broken
+    }
+}
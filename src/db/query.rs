@@ -0,0 +1,416 @@
+
+//! typed queries
+//!
+//! Queries are SQL files (in `sql/`) with named parameters (`@from`), bound by
+//! name when run. Rows are decoded into [`SqlRow`]s, and mapped to a type with
+//! [`FromRow`] once its required columns are checked.
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+use tiberius::{ColumnData, FromSql, ToSql};
+use time::PrimitiveDateTime;
+
+use super::{BurnedPart, Confirmation, LoadedPart, PartBurnedQty, Program, Remnant, SheetStock};
+use crate::api::Qty;
+use crate::{Error, Result};
+
+static PARAM   : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@(\w+)").expect("Failed to build PARAM regex") );
+static DECLARE : LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\bDECLARE\s+@(\w+)").expect("Failed to build DECLARE regex") );
+
+/// A value of a column
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// SQL `NULL`
+    Null,
+    /// any integer type
+    Int(i64),
+    /// any floating point or decimal type
+    Float(f64),
+    /// `bit`
+    Bool(bool),
+    /// any string type
+    Str(String),
+    /// any date and time type
+    DateTime(PrimitiveDateTime),
+}
+
+impl Value {
+    pub(super) fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null        => "null",
+            Self::Int(_)      => "int",
+            Self::Float(_)    => "float",
+            Self::Bool(_)     => "bool",
+            Self::Str(_)      => "string",
+            Self::DateTime(_) => "datetime",
+        }
+    }
+
+    /// reads the value of a parameter
+    pub(super) fn from_param(name: &str, param: &dyn ToSql) -> Result<Self> {
+        Self::from_column(name, &param.to_sql())
+    }
+
+    fn from_column(column: &str, data: &ColumnData<'_>) -> Result<Self> {
+        let value = match data {
+            ColumnData::U8(val)  => val.map(|v| Self::Int(v as i64)),
+            ColumnData::I16(val) => val.map(|v| Self::Int(v as i64)),
+            ColumnData::I32(val) => val.map(|v| Self::Int(v as i64)),
+            ColumnData::I64(val) => val.map(Self::Int),
+            ColumnData::F32(val) => val.map(|v| Self::Float(v as f64)),
+            ColumnData::F64(val) => val.map(Self::Float),
+            ColumnData::Numeric(val) => val.map(|v| Self::Float(v.into())),
+            ColumnData::Bit(val) => val.map(Self::Bool),
+            ColumnData::String(val) => val.as_ref().map(|v| Self::Str(v.to_string())),
+
+            ColumnData::DateTime(val)      => Self::datetime(column, ColumnData::DateTime(*val))?,
+            ColumnData::SmallDateTime(val) => Self::datetime(column, ColumnData::SmallDateTime(*val))?,
+            ColumnData::DateTime2(val)     => Self::datetime(column, ColumnData::DateTime2(*val))?,
+
+            _ => return Err( Error::Query(format!("unsupported type for column `{}`", column)) )
+        };
+
+        Ok(value.unwrap_or(Self::Null))
+    }
+
+    fn datetime(column: &str, data: ColumnData<'static>) -> Result<Option<Self>> {
+        PrimitiveDateTime::from_sql(&data)
+            .map(|val| val.map(Self::DateTime))
+            .map_err(Error::db(format!("reading column `{}`", column)))
+    }
+}
+
+/// Conversion from a column [`Value`]
+pub trait FromValue: Sized {
+    /// converts the value, `None` if it is `NULL`
+    fn from_value(value: &Value) -> Option<std::result::Result<Self, ()>>;
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Option<std::result::Result<Self, ()>> {
+        match value {
+            Value::Null => None,
+            Value::Str(val) => Some(Ok(val.clone())),
+            _ => Some(Err(()))
+        }
+    }
+}
+
+impl FromValue for i32 {
+    fn from_value(value: &Value) -> Option<std::result::Result<Self, ()>> {
+        match value {
+            Value::Null => None,
+            Value::Int(val) => Some(i32::try_from(*val).map_err(|_| ())),
+            _ => Some(Err(()))
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Option<std::result::Result<Self, ()>> {
+        match value {
+            Value::Null => None,
+            Value::Float(val) => Some(Ok(*val)),
+            Value::Int(val) => Some(Ok(*val as f64)),
+            _ => Some(Err(()))
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Option<std::result::Result<Self, ()>> {
+        match value {
+            Value::Null => None,
+            Value::Bool(val) => Some(Ok(*val)),
+            _ => Some(Err(()))
+        }
+    }
+}
+
+impl FromValue for PrimitiveDateTime {
+    fn from_value(value: &Value) -> Option<std::result::Result<Self, ()>> {
+        match value {
+            Value::Null => None,
+            Value::DateTime(val) => Some(Ok(*val)),
+            _ => Some(Err(()))
+        }
+    }
+}
+
+/// A row of a query result
+#[derive(Debug, Clone)]
+pub struct SqlRow {
+    columns: Arc<[String]>,
+    values: Vec<Value>,
+}
+
+impl SqlRow {
+    /// creates a row from column names and values
+    pub fn new(columns: Arc<[String]>, values: Vec<Value>) -> Self {
+        Self { columns, values }
+    }
+
+    /// the column names
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// the values, in the same order as the columns
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// gets the value of a column that must not be `NULL`
+    pub fn require<T: FromValue>(&self, column: &str) -> Result<T> {
+        self.get(column)?
+            .ok_or_else(|| Error::Query(format!("column `{}` is NULL", column)))
+    }
+
+    /// gets the value of a column, `None` if it is `NULL`
+    pub fn get<T: FromValue>(&self, column: &str) -> Result<Option<T>> {
+        let value = self.columns
+            .iter()
+            .position(|col| col == column)
+            .map(|i| &self.values[i])
+            .ok_or_else(|| Error::Query(format!("no column `{}`", column)))?;
+
+        match T::from_value(value) {
+            None => Ok(None),
+            Some(Ok(val)) => Ok(Some(val)),
+            Some(Err(())) => Err( Error::Query(format!(
+                "column `{}` has {} value, expected {}", column, value.type_name(), std::any::type_name::<T>()
+            )) ),
+        }
+    }
+}
+
+impl TryFrom<tiberius::Row> for SqlRow {
+    type Error = Error;
+
+    fn try_from(row: tiberius::Row) -> Result<Self> {
+        let columns: Arc<[String]> = row.columns().iter().map(|col| col.name().to_string()).collect();
+        let values = columns
+            .iter()
+            .zip(row)
+            .map(|(col, data)| Value::from_column(col, &data))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { columns, values })
+    }
+}
+
+/// A type that can be read from a query row
+pub trait FromRow: Sized {
+    /// the columns the row must have
+    const COLUMNS: &'static [&'static str];
+
+    /// reads the type from a row that has all of [`FromRow::COLUMNS`]
+    fn from_row(row: &SqlRow) -> Result<Self>;
+}
+
+/// joins the columns of a row with the columns of a type nested in it
+///
+/// `N` must be the total number of columns, e.g.
+/// `&columns::<{ 2 + MaterialData::COLUMNS.len() }>(&["Part", "Qty"], MaterialData::COLUMNS)`.
+pub const fn columns<const N: usize>(own: &[&'static str], nested: &[&'static str]) -> [&'static str; N] {
+    assert!(own.len() + nested.len() == N, "wrong number of columns");
+
+    let mut cols = [""; N];
+    let mut i = 0;
+    while i < own.len() {
+        cols[i] = own[i];
+        i += 1;
+    }
+    while i < N {
+        cols[i] = nested[i - own.len()];
+        i += 1;
+    }
+
+    cols
+}
+
+/// checks that the result has all the columns a type needs
+pub fn check_columns<T: FromRow>(columns: &[String]) -> Result<()> {
+    let missing: Vec<&str> = T::COLUMNS
+        .iter()
+        .filter(|col| !columns.iter().any(|c| c == *col))
+        .copied()
+        .collect();
+
+    match missing.is_empty() {
+        true  => Ok(()),
+        false => Err( Error::Query(format!("missing columns for {}: {}", std::any::type_name::<T>(), missing.join(", "))) )
+    }
+}
+
+/// A SQL query with named parameters, returning rows of `T`
+#[derive(Debug)]
+pub struct Query<T> {
+    /// the name of the SQL file
+    pub name: &'static str,
+    /// the SQL text
+    pub sql: &'static str,
+    /// the named parameters, without the `@`
+    pub params: &'static [&'static str],
+    row: PhantomData<fn() -> T>,
+}
+
+impl<T: FromRow> Query<T> {
+    /// creates a query
+    pub const fn new(name: &'static str, sql: &'static str, params: &'static [&'static str]) -> Self {
+        Self { name, sql, params, row: PhantomData }
+    }
+
+    /// the SQL with the named parameters replaced by positional ones (`@P1`, `@P2`, ...)
+    pub fn positional_sql(&self) -> String {
+        PARAM.replace_all(self.sql, |caps: &regex::Captures| {
+            match self.params.iter().position(|param| *param == &caps[1]) {
+                Some(i) => format!("@P{}", i + 1),
+                None => caps[0].to_string()
+            }
+        }).into_owned()
+    }
+
+    /// orders named parameter values by their position in the query
+    pub fn bind<'a>(&self, params: &[(&str, &'a dyn ToSql)]) -> Result<Vec<&'a dyn ToSql>> {
+        if let Some((name, _)) = params.iter().find(|(name, _)| !self.params.contains(name)) {
+            return Err( Error::Query(format!("query `{}` has no parameter `@{}`", self.name, name)) );
+        }
+
+        self.params
+            .iter()
+            .map(|param| params
+                .iter()
+                .find(|(name, _)| name == param)
+                .map(|(_, val)| *val)
+                .ok_or_else(|| Error::Query(format!("query `{}` is missing parameter `@{}`", self.name, param)))
+            )
+            .collect()
+    }
+}
+
+/// named parameters used in SQL (excluding declared variables)
+pub fn sql_params(sql: &str) -> HashSet<&str> {
+    let declared: HashSet<&str> = DECLARE
+        .captures_iter(sql)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str()))
+        .collect();
+
+    PARAM
+        .captures_iter(sql)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str()))
+        .filter(|param| !declared.contains(param))
+        .collect()
+}
+
+macro_rules! query {
+    ($name:ident: $row:ty = $file:literal $(, $param:literal)*) => {
+        #[doc = concat!("`sql/", $file, "`")]
+        pub const $name: Query<$row> = Query::new($file, include_str!(concat!("sql/", $file)), &[$($param),*]);
+    };
+}
+
+query!(PARTS_BURNED_FOR_WEEK : BurnedPart    = "get_parts_burned_for_week.sql");
+query!(PARTS_BURNED          : BurnedPart    = "get_parts_burned.sql", "from", "to");
+query!(PARTS_BURNED_SINCE    : BurnedPart    = "get_parts_burned_since.sql", "packet_id", "archived");
+query!(PART_BURNED_QTY       : Qty           = "get_part_burned_qty.sql", "part");
+query!(PARTS_BURNED_QTY      : PartBurnedQty = "get_parts_burned_qty.sql");
+query!(PART_BURNED_BY_PLANT  : PartBurnedQty = "get_part_burned_by_plant.sql", "part");
+query!(PROGRAMS              : Program       = "get_programs.sql", "from", "to");
+query!(NESTED_PROGRAMS       : Program       = "get_nested_programs.sql");
+query!(REMNANTS              : Remnant       = "get_remnants.sql", "from", "to");
+query!(LOADED_PARTS          : LoadedPart    = "get_loaded_parts.sql");
+query!(STOCK                 : SheetStock    = "get_stock.sql");
+query!(CONFIRMATIONS         : Confirmation  = "sap_cnf_swaldon.sql");
+
+impl FromRow for Qty {
+    const COLUMNS: &'static [&'static str] = &["Qty"];
+
+    fn from_row(row: &SqlRow) -> Result<Self> {
+        Qty::try_from( row.require::<i32>("Qty")? )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::Path;
+
+    /// (name, sql, params, columns) of all the queries
+    fn queries() -> Vec<(&'static str, &'static str, &'static [&'static str], &'static [&'static str])> {
+        fn info<T: FromRow>(query: &Query<T>) -> (&'static str, &'static str, &'static [&'static str], &'static [&'static str]) {
+            (query.name, query.sql, query.params, T::COLUMNS)
+        }
+
+        vec![
+            info(&PARTS_BURNED_FOR_WEEK),
+            info(&PARTS_BURNED),
+            info(&PARTS_BURNED_SINCE),
+            info(&PART_BURNED_QTY),
+            info(&PARTS_BURNED_QTY),
+            info(&PART_BURNED_BY_PLANT),
+            info(&PROGRAMS),
+            info(&NESTED_PROGRAMS),
+            info(&REMNANTS),
+            info(&LOADED_PARTS),
+            info(&STOCK),
+            info(&CONFIRMATIONS),
+        ]
+    }
+
+    #[test]
+    fn every_sql_file_has_a_query() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/db/sql");
+        let names: HashSet<&str> = queries().into_iter().map(|(name, ..)| name).collect();
+
+        for entry in fs::read_dir(dir).unwrap() {
+            let file = entry.unwrap().file_name().into_string().unwrap();
+            assert!(names.contains(file.as_str()), "no query defined for `{}`", file);
+        }
+    }
+
+    #[test]
+    fn queries_bind_their_parameters() {
+        for (name, sql, params, _) in queries() {
+            let used = sql_params(sql);
+            let declared: HashSet<&str> = params.iter().copied().collect();
+
+            assert_eq!(used, declared, "parameters of `{}`", name);
+        }
+    }
+
+    #[test]
+    fn null_required_column_is_an_error() {
+        let columns: Arc<[String]> = SheetStock::COLUMNS.iter().map(|col| col.to_string()).collect();
+        let row = |wbs: Value, matl: Value| SqlRow::new(columns.clone(), vec![
+            Value::Str("S1".into()), Value::Float(120.0), Value::Float(96.0), Value::Int(1), Value::Bool(false),
+            matl, wbs, Value::Null, Value::Str("HS01".into()), Value::Float(11520.0),
+        ]);
+
+        let sheet = SheetStock::from_row(&row(Value::Null, Value::Str("50/50W-0500".into()))).unwrap();
+        assert_eq!(sheet.matl.wbs, None);
+        assert_eq!(sheet.matl.loc, "");
+
+        match SheetStock::from_row(&row(Value::Null, Value::Null)) {
+            Err(Error::Query(msg)) => assert!(msg.contains("MaterialMaster"), "{}", msg),
+            res => panic!("expected a query error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn queries_select_their_columns() {
+        for (name, sql, _, columns) in queries() {
+            for col in columns {
+                let selected = Regex::new(&format!(r"(?i)(\bAS\s+|[\w')]\s+|\.){}\s*(,|$|\bFROM\b)", col)).unwrap();
+                let selected = sql.lines().any(|line| selected.is_match(line.trim_end()));
+
+                assert!(selected, "`{}` does not select column `{}`", name, col);
+            }
+        }
+    }
+}
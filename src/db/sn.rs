@@ -1,78 +1,282 @@
 
-use ftlog::{info, trace};
-use std::env;
-use tiberius::{Client, Config, AuthMethod};
-use tokio::net::TcpStream;
-use tokio_util::compat::TokioAsyncWriteCompatExt;
+use ftlog::{info, trace, warn};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tiberius::{Config, Row, ToSql};
 
-use super::BurnedPart;
+use time::Date;
+
+use super::config;
+use super::pool::MssqlManager;
+use super::query::{self, check_columns, FromRow, Query, SqlRow, Value};
+use super::snapshot::{Recorder, Snapshot};
+use super::{BurnedPart, Confirmation, LoadedPart, PartBurnedQty, Program, Remnant, SheetStock, Since};
+use crate::{Error, Result};
+
+/// maximum number of connections kept open
+const POOL_SIZE: u32 = 4;
+/// time allowed to open a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// default time allowed for a query to complete
+const QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+/// number of times a query is retried after a transient error
+const MAX_RETRIES: u32 = 3;
+/// delay before the first retry, doubled for each retry after that
+const BACKOFF: Duration = Duration::from_millis(500);
+
+/// SQL Server error code for a transaction chosen as a deadlock victim
+const DEADLOCK_VICTIM: u32 = 1205;
 
 /// Sigmanest database interface
-#[derive(Debug)]
+///
+/// Queries run on a pool of connections, so `Sndb` can be shared between tasks.
+/// Connections are checked before use and queries that fail with a transient
+/// error (dropped connection, deadlock, timeout) are retried.
+///
+/// Query results can be recorded to a [`Snapshot`] file (see [`Sndb::record_to`])
+/// and replayed later without a database (see [`Sndb::replay`]).
+#[derive(Debug, Clone)]
 pub struct Sndb {
-    conn: super::MssqlClient
+    source: Source,
+    timeout: Duration,
+    recorder: Option<Arc<Recorder>>,
+}
+
+/// where query results come from
+#[derive(Debug, Clone)]
+enum Source {
+    /// a pool of SQL Server connections
+    Pool(bb8::Pool<MssqlManager>),
+    /// results recorded in a snapshot
+    Replay(Arc<Snapshot>),
 }
 
 impl Sndb {
-    /// Initialize Sigmanest database connection
-    pub async fn init() -> tiberius::Result<Self> {
+    /// Initialize Sigmanest database connection, configured from the environment
+    /// 
+    /// see [`config::from_env`] for the variables used
+    pub async fn init() -> Result<Self> {
         info!(">> initializing Sigmanest database connector");
-        
+
         trace!("building config");
-        let user = env::var("SNDB_USER").expect("environment variable `SNDB_USER` not defined");
-        let pass = env::var("SNDB_PWD").expect("environment variable `SNDB_PWD` not defined");
-        let mut config = Config::new();
-        config.host("hiiwinbl18");
-        config.database("SNDBase91");
-        config.authentication(AuthMethod::sql_server(user, pass));
-        config.trust_cert(); // on production, it is not a good idea to do this
-    
-        trace!("opening TCP stream");
-        let tcp = TcpStream::connect(config.get_addr()).await?;
-        tcp.set_nodelay(true)?;
-    
-        // To be able to use Tokio's tcp, we're using the `compat_write` from
-        // the `TokioAsyncWriteCompatExt` to get a stream compatible with the
-        // traits from the `futures` crate.
-        let conn = Client::connect(config, tcp.compat_write()).await?;
+        Self::connect(config::from_env()?).await
+    }
+
+    /// Initialize Sigmanest database connection with a given config
+    pub async fn connect(config: Config) -> Result<Self> {
+        let pool = bb8::Pool::builder()
+            .max_size(POOL_SIZE)
+            .connection_timeout(CONNECT_TIMEOUT)
+            .test_on_check_out(true)
+            .build_unchecked(MssqlManager::new(config));
+
+        // make sure we can connect before handing out the pool,
+        // outside of the pool so the connection error is not swallowed by its retries
+        let conn = pool.dedicated_connection().await.map_err(Error::db("connecting to Sigmanest"))?;
+        // the pool is empty, so there is always room for it
+        let _ = pool.add(conn);
 
         info!(">> Sigmanest connection successful");
-    
-        Ok( Self { conn } )
+
+        Ok( Self { source: Source::Pool(pool), timeout: QUERY_TIMEOUT, recorder: None } )
+    }
+
+    /// Replays the query results recorded in a snapshot file
+    ///
+    /// Queries that were not recorded with the same parameters fail with [`Error::Query`].
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        info!(">> replaying Sigmanest queries from `{}`", path.display());
+
+        Ok( Self::from_snapshot(Snapshot::load(path)?) )
+    }
+
+    /// Replays the query results recorded in a snapshot
+    pub fn from_snapshot(snapshot: Snapshot) -> Self {
+        Self { source: Source::Replay(Arc::new(snapshot)), timeout: QUERY_TIMEOUT, recorder: None }
+    }
+
+    /// records the results of each query to a snapshot file
+    ///
+    /// The file is rewritten after each query, so it is complete even if the program exits early.
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.recorder = Some(Arc::new(Recorder::new(path.into())));
+
+        self
+    }
+
+    /// sets the time allowed for each query to complete
+    pub fn with_query_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// runs a query with named parameters, reading the rows of the first result set as `T`
+    async fn fetch<T: FromRow>(&self, context: &str, query: &Query<T>, params: &[(&str, &dyn ToSql)]) -> Result<Vec<T>> {
+        let bound = query.bind(params)?;
+        let rows = match &self.source {
+            Source::Pool(pool) => self.query(pool, context, &query.positional_sql(), &bound).await?,
+            Source::Replay(snapshot) => {
+                let values = param_values(params)?;
+                snapshot
+                    .get(query.name, &values)
+                    .ok_or_else(|| Error::Query(format!("no recording of `{}` with parameters {:?}", query.name, values)))?
+                    .rows()?
+            },
+        };
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record(query.name, &param_values(params)?, &rows)?;
+        }
+
+        if let Some(row) = rows.first() {
+            check_columns::<T>(row.columns())
+                .map_err(|e| Error::Query(format!("`{}`: {}", query.name, e)))?;
+        }
+
+        rows.iter().map(T::from_row).collect()
+    }
+
+    /// runs a query, returning the rows of the first result set
+    ///
+    /// Transient errors and timeouts are retried with an exponential backoff.
+    async fn query(&self, pool: &bb8::Pool<MssqlManager>, context: &str, sql: &str, params: &[&dyn ToSql]) -> Result<Vec<SqlRow>> {
+        let mut attempt = 0;
+        loop {
+            let err = match tokio::time::timeout(self.timeout, try_query(pool, sql, params)).await {
+                Ok(Ok(rows)) => return rows.into_iter().map(SqlRow::try_from).collect(),
+                Ok(Err(e)) if is_transient(&e) => Error::db(context)(e),
+                Ok(Err(e)) => return Err( Error::db(context)(e) ),
+                Err(_) => Error::Timeout(context.into()),
+            };
+
+            if attempt >= MAX_RETRIES {
+                return Err(err);
+            }
+
+            attempt += 1;
+            let delay = BACKOFF * 2u32.pow(attempt - 1);
+            warn!("{} (retry {} of {} in {:?})", err, attempt, MAX_RETRIES, delay);
+            tokio::time::sleep(delay).await;
+        }
     }
 
     /// get all the parts burned in Sigmanest for the past week
-    pub async fn get_parts_burned_for_week(&mut self) -> tiberius::Result<Vec<BurnedPart>> {
+    pub async fn get_parts_burned_for_week(&self) -> Result<Vec<BurnedPart>> {
         trace!("fetching parts burned in the previous week");
-        let results = self.conn
-            .simple_query(include_str!("sql/get_parts_burned_for_week.sql"))
-            .await?
-            .into_first_result()
-            .await?;
-        
-        let mut res = Vec::<BurnedPart>::new();
-        for x in results {
-            res.push(BurnedPart::try_from(&x)?)
-        }
+        self.fetch("fetching parts burned for the week", &query::PARTS_BURNED_FOR_WEEK, &[]).await
+    }
+
+    /// get all the parts burned in Sigmanest between two dates (`to` is exclusive)
+    pub async fn get_parts_burned(&self, from: Date, to: Date) -> Result<Vec<BurnedPart>> {
+        trace!("fetching parts burned from {} to {}", from, to);
+        self.fetch("fetching parts burned", &query::PARTS_BURNED, &[("from", &from.midnight()), ("to", &to.midnight())]).await
+    }
+
+    /// get all the parts burned in Sigmanest after a position in the archive, in archive order
+    pub async fn get_parts_burned_since(&self, since: Since) -> Result<Vec<BurnedPart>> {
+        trace!("fetching parts burned since {:?}", since);
+        let (packet_id, time) = since.params();
+        self.fetch("fetching new parts burned", &query::PARTS_BURNED_SINCE, &[("packet_id", &packet_id), ("archived", &time)]).await
+    }
+
+    /// get all the programs burned in Sigmanest between two dates (`to` is exclusive)
+    pub async fn get_programs(&self, from: Date, to: Date) -> Result<Vec<Program>> {
+        trace!("fetching programs burned from {} to {}", from, to);
+        self.fetch("fetching programs", &query::PROGRAMS, &[("from", &from.midnight()), ("to", &to.midnight())]).await
+    }
+
+    /// get the remnants cut from programs burned between two dates (`to` is exclusive)
+    pub async fn get_remnants(&self, from: Date, to: Date) -> Result<Vec<Remnant>> {
+        trace!("fetching remnants from {} to {}", from, to);
+        self.fetch("fetching remnants", &query::REMNANTS, &[("from", &from.midnight()), ("to", &to.midnight())]).await
+    }
+
+    /// get the programs nested in Sigmanest that have not been burned yet
+    pub async fn get_nested_programs(&self) -> Result<Vec<Program>> {
+        trace!("fetching nested programs");
+        self.fetch("fetching nested programs", &query::NESTED_PROGRAMS, &[]).await
+    }
+
+    /// get the parts loaded into Sigmanest on work orders
+    pub async fn get_loaded_parts(&self) -> Result<Vec<LoadedPart>> {
+        trace!("fetching loaded parts");
+        self.fetch("fetching loaded parts", &query::LOADED_PARTS, &[]).await
+    }
+
+    /// get the sheets in the Sigmanest inventory, including remnants
+    pub async fn get_stock(&self) -> Result<Vec<SheetStock>> {
+        trace!("fetching stock");
+        self.fetch("fetching stock", &query::STOCK, &[]).await
+    }
 
-        Ok(res)
+    /// get the confirmations to upload to SAP for recent burns
+    pub async fn get_confirmations(&self) -> Result<Vec<Confirmation>> {
+        trace!("fetching confirmations");
+        self.fetch("fetching confirmations", &query::CONFIRMATIONS, &[]).await
     }
 
     /// get the number of pieces burned for a given `part` name
-    pub async fn get_part_burned_qty(&mut self, part: &str) -> tiberius::Result<i32> {
+    pub async fn get_part_burned_qty(&self, part: &str) -> Result<i32> {
         trace!("fetching part burned quantity for `{}`", part);
 
-        match self.conn
-            .query(
-                "select isnull( sum(QtyProgram), 0 ) from PartArchive where PartName=@P1",
-                &[&part]
-            )
-                .await?
-            .into_row()
-                .await?.unwrap() // should not panic because of `isnull` in sql statement
-            .get(0) {
-                Some(val) => Ok(val),
-                None => Ok(0)
-            }
+        // a row is always returned because of `isnull` in sql statement
+        let qty = self.fetch("fetching part burned quantity", &query::PART_BURNED_QTY, &[("part", &part)]).await?;
+
+        Ok( qty.first().map(|qty| qty.0 as i32).unwrap_or_default() )
+    }
+
+    /// get the number of pieces burned to date of a given `part` name, by plant and material WBS element
+    pub async fn get_part_burned_by_plant(&self, part: &str) -> Result<Vec<PartBurnedQty>> {
+        trace!("fetching part burned quantities by plant for `{}`", part);
+        self.fetch("fetching part burned quantities by plant", &query::PART_BURNED_BY_PLANT, &[("part", &part)]).await
+    }
+
+    /// get the number of pieces burned to date of every part, by plant and material WBS element
+    pub async fn get_parts_burned_qty(&self) -> Result<Vec<PartBurnedQty>> {
+        trace!("fetching parts burned quantities");
+        self.fetch("fetching parts burned quantities", &query::PARTS_BURNED_QTY, &[]).await
+    }
+}
+
+/// runs a query on a pooled connection
+async fn try_query(pool: &bb8::Pool<MssqlManager>, sql: &str, params: &[&dyn ToSql]) -> tiberius::Result<Vec<Row>> {
+    let mut conn = match pool.get().await {
+        Ok(conn) => conn,
+        Err(bb8::RunError::User(e)) => return Err(e),
+        Err(bb8::RunError::TimedOut) => return Err(tiberius::error::Error::Io {
+            kind: ErrorKind::TimedOut,
+            message: "timed out waiting for a connection".into(),
+        }),
+    };
+
+    // marked as broken until the whole result is read
+    conn.broken = true;
+    let stream = match params.is_empty() {
+        true  => conn.client.simple_query(sql).await?,
+        false => conn.client.query(sql, params).await?,
+    };
+    let rows = stream.into_first_result().await?;
+    conn.broken = false;
+
+    Ok(rows)
+}
+
+/// reads the values of named parameters, to record or look up a query by
+fn param_values(params: &[(&str, &dyn ToSql)]) -> Result<Vec<(String, Value)>> {
+    params
+        .iter()
+        .map(|(name, val)| Ok( (name.to_string(), Value::from_param(name, *val)?) ))
+        .collect()
+}
+
+/// if an error may succeed when retried
+fn is_transient(err: &tiberius::error::Error) -> bool {
+    match err {
+        tiberius::error::Error::Io { .. } => true,
+        _ => err.code() == Some(DEADLOCK_VICTIM)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,301 @@
+
+//! recorded query results
+//!
+//! A [`Snapshot`] holds the rows returned by each query (keyed by query name
+//! and parameter values), so a [`Sndb`](super::Sndb) can replay them without
+//! a SQL Server connection. Snapshots are saved as JSON, with the type of
+//! each column recorded so values are read back exactly as they were returned.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use time::PrimitiveDateTime;
+
+use super::query::{SqlRow, Value};
+use crate::{state, Error, Result};
+
+/// The type of a recorded column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    /// every value in the column was `NULL`
+    Null,
+    /// integer
+    Int,
+    /// floating point or decimal
+    Float,
+    /// `bit`
+    Bool,
+    /// string
+    String,
+    /// date and time
+    DateTime,
+}
+
+impl ColumnType {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Null        => Self::Null,
+            Value::Int(_)      => Self::Int,
+            Value::Float(_)    => Self::Float,
+            Value::Bool(_)     => Self::Bool,
+            Value::Str(_)      => Self::String,
+            Value::DateTime(_) => Self::DateTime,
+        }
+    }
+
+    fn to_json(value: &Value) -> serde_json::Value {
+        match value {
+            Value::Null          => serde_json::Value::Null,
+            Value::Int(val)      => (*val).into(),
+            Value::Float(val)    => (*val).into(),
+            Value::Bool(val)     => (*val).into(),
+            Value::Str(val)      => val.as_str().into(),
+            Value::DateTime(val) => serde_json::to_value(val).unwrap_or_default(),
+        }
+    }
+
+    fn read(self, json: &serde_json::Value) -> Option<Value> {
+        if json.is_null() {
+            return Some(Value::Null);
+        }
+
+        match self {
+            Self::Null     => None,
+            Self::Int      => json.as_i64().map(Value::Int),
+            Self::Float    => json.as_f64().map(Value::Float),
+            Self::Bool     => json.as_bool().map(Value::Bool),
+            Self::String   => json.as_str().map(|val| Value::Str(val.into())),
+            Self::DateTime => serde_json::from_value::<PrimitiveDateTime>(json.clone()).ok().map(Value::DateTime),
+        }
+    }
+}
+
+/// A recorded column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Column {
+    /// column name
+    pub name: String,
+    /// type of the values in the column
+    #[serde(rename = "type")]
+    pub kind: ColumnType,
+}
+
+/// A parameter value, with its type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Param {
+    #[serde(rename = "type")]
+    kind: ColumnType,
+    value: serde_json::Value,
+}
+
+/// The rows returned by one run of a query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    /// name of the query (SQL file)
+    pub query: String,
+    params: BTreeMap<String, Param>,
+    /// columns of the result
+    pub columns: Vec<Column>,
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+impl Recording {
+    fn new(query: &str, params: &[(String, Value)], rows: &[SqlRow]) -> Self {
+        let columns = rows
+            .first()
+            .map(SqlRow::columns)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Column {
+                name: name.clone(),
+                kind: rows
+                    .iter()
+                    .map(|row| ColumnType::of(&row.values()[i]))
+                    .find(|kind| *kind != ColumnType::Null)
+                    .unwrap_or(ColumnType::Null),
+            })
+            .collect();
+
+        Self {
+            query: query.into(),
+            params: params_map(params),
+            columns,
+            rows: rows
+                .iter()
+                .map(|row| row.values().iter().map(ColumnType::to_json).collect())
+                .collect(),
+        }
+    }
+
+    /// the recorded rows
+    pub fn rows(&self) -> Result<Vec<SqlRow>> {
+        let names: Arc<[String]> = self.columns.iter().map(|col| col.name.clone()).collect();
+
+        self.rows
+            .iter()
+            .map(|row| {
+                if row.len() != self.columns.len() {
+                    return Err( Error::Query(format!(
+                        "recording of `{}` has a row with {} values for {} columns", self.query, row.len(), self.columns.len()
+                    )) );
+                }
+
+                let values = self.columns
+                    .iter()
+                    .zip(row)
+                    .map(|(col, json)| col.kind.read(json).ok_or_else(|| Error::Query(format!(
+                        "recording of `{}` has {} in {:?} column `{}`", self.query, json, col.kind, col.name
+                    ))))
+                    .collect::<Result<_>>()?;
+
+                Ok( SqlRow::new(names.clone(), values) )
+            })
+            .collect()
+    }
+}
+
+fn params_map(params: &[(String, Value)]) -> BTreeMap<String, Param> {
+    params
+        .iter()
+        .map(|(name, value)| (name.clone(), Param { kind: ColumnType::of(value), value: ColumnType::to_json(value) }))
+        .collect()
+}
+
+/// Recorded query results
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// each query run, by query name and parameters
+    pub queries: Vec<Recording>,
+}
+
+impl Snapshot {
+    /// loads a snapshot from `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(Error::io(format!("reading snapshot `{}`", path.display())))?;
+
+        serde_json::from_str(&text).map_err(|e| Error::parse("snapshot", e))
+    }
+
+    /// saves the snapshot to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        state::save(self, path.as_ref(), "snapshot")
+    }
+
+    /// the recording of a query run with the given parameters
+    pub fn get(&self, query: &str, params: &[(String, Value)]) -> Option<&Recording> {
+        let params = params_map(params);
+
+        self.queries
+            .iter()
+            .find(|rec| rec.query == query && rec.params == params)
+    }
+
+    /// records the rows of a query, replacing an earlier run with the same parameters
+    pub fn insert(&mut self, query: &str, params: &[(String, Value)], rows: &[SqlRow]) {
+        let recording = Recording::new(query, params, rows);
+
+        match self.queries.iter_mut().find(|rec| rec.query == query && rec.params == recording.params) {
+            Some(rec) => *rec = recording,
+            None => self.queries.push(recording),
+        }
+    }
+}
+
+/// Records query results to a snapshot file as they are returned
+#[derive(Debug)]
+pub(super) struct Recorder {
+    path: PathBuf,
+    snapshot: Mutex<Snapshot>,
+}
+
+impl Recorder {
+    pub(super) fn new(path: PathBuf) -> Self {
+        Self { path, snapshot: Mutex::new(Snapshot::default()) }
+    }
+
+    /// records the rows of a query and saves the snapshot
+    pub(super) fn record(&self, query: &str, params: &[(String, Value)], rows: &[SqlRow]) -> Result<()> {
+        let mut snapshot = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        snapshot.insert(query, params, rows);
+
+        snapshot.save(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::{Date, Month};
+
+    fn datetime(day: u8, hour: u8, min: u8, sec: u8, milli: u16) -> PrimitiveDateTime {
+        Date::from_calendar_date(2026, Month::October, day)
+            .and_then(|date| date.with_hms_milli(hour, min, sec, milli))
+            .unwrap()
+    }
+
+    fn rows() -> Vec<SqlRow> {
+        let columns: Arc<[String]> = ["Part", "Qty", "Area", "Remnant", "ArcDateTime", "Wbs"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        vec![
+            SqlRow::new(columns.clone(), vec![
+                Value::Str("1200001A-X1A".into()),
+                Value::Int(4),
+                Value::Float(1234.5),
+                Value::Bool(false),
+                Value::DateTime(datetime(12, 6, 31, 2, 500)),
+                Value::Null,
+            ]),
+            SqlRow::new(columns, vec![
+                Value::Str("1200001A-X2".into()),
+                Value::Int(1),
+                Value::Float(12.0),
+                Value::Bool(true),
+                Value::Null,
+                Value::Null,
+            ]),
+        ]
+    }
+
+    #[test]
+    fn rows_replay_as_recorded() {
+        let params = vec![("from".to_string(), Value::DateTime(datetime(12, 0, 0, 0, 0)))];
+        let mut snapshot = Snapshot::default();
+        snapshot.insert("get_parts_burned.sql", &params, &rows());
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        let replayed = snapshot.get("get_parts_burned.sql", &params).unwrap().rows().unwrap();
+
+        assert_eq!(snapshot.queries[0].columns.iter().map(|col| col.kind).collect::<Vec<_>>(), [
+            ColumnType::String, ColumnType::Int, ColumnType::Float, ColumnType::Bool, ColumnType::DateTime, ColumnType::Null
+        ]);
+        for (replayed, recorded) in replayed.iter().zip(rows()) {
+            assert_eq!(replayed.columns(), recorded.columns());
+            assert_eq!(replayed.values(), recorded.values());
+        }
+    }
+
+    #[test]
+    fn recordings_are_keyed_by_params() {
+        let week = |day| vec![("from".to_string(), Value::Str(day))];
+        let mut snapshot = Snapshot::default();
+        snapshot.insert("get_programs.sql", &week("monday".into()), &rows());
+        snapshot.insert("get_programs.sql", &week("tuesday".into()), &rows()[..1]);
+        snapshot.insert("get_programs.sql", &week("monday".into()), &[]);
+
+        assert_eq!(snapshot.queries.len(), 2);
+        assert!(snapshot.get("get_programs.sql", &week("monday".into())).unwrap().rows().unwrap().is_empty());
+        assert_eq!(snapshot.get("get_programs.sql", &week("tuesday".into())).unwrap().rows().unwrap().len(), 1);
+        assert!(snapshot.get("get_programs.sql", &week("friday".into())).is_none());
+        assert!(snapshot.get("get_remnants.sql", &week("monday".into())).is_none());
+    }
+}
@@ -0,0 +1,20 @@
+SELECT
+    REPLACE(part.PartName, '_', '-') AS Part,
+    part.WONumber,
+    part.QtyOrdered AS QtyRequired,
+    part.QtyCompleted AS QtyBurned,
+    ISNULL(pip.QtyNested, 0) AS QtyNested,
+    part.Data1,
+    part.Data2
+FROM Part AS part
+    LEFT JOIN (
+        SELECT
+            WONumber,
+            PartName,
+            SUM(QtyInProcess) AS QtyNested
+        FROM PIP
+        GROUP BY WONumber, PartName
+    ) AS pip
+        ON part.WONumber=pip.WONumber
+        AND part.PartName=pip.PartName
+ORDER BY part.PartName
\ No newline at end of file
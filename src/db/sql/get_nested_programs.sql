@@ -0,0 +1,34 @@
+SELECT
+    program.ProgramName AS Program,
+    program.RepeatID,
+    program.MachineName AS Machine,
+    CAST(NULL AS datetime) AS ArcDateTime,
+
+    stock.SheetName AS Sheet,
+    stock.Location,
+    stock.PrimeCode AS MaterialMaster,
+    NULLIF(stock.Mill,'') AS Wbs,
+    stock.Area,
+
+    parts.Qty,
+    parts.NestedArea,
+
+    CASE LEFT(program.MachineName,7)
+        WHEN 'Plant_3' THEN 'HS02'
+        ELSE 'HS01'
+    END AS Plant
+FROM Program AS program
+    INNER JOIN Stock AS stock
+        ON program.SheetName=stock.SheetName
+    INNER JOIN (
+        SELECT
+            ProgramName,
+            RepeatID,
+            SUM(QtyInProcess) AS Qty,
+            SUM(NestedArea * QtyInProcess) AS NestedArea
+        FROM PIP
+        GROUP BY ProgramName, RepeatID
+    ) AS parts
+        ON program.ProgramName=parts.ProgramName
+        AND program.RepeatID=parts.RepeatID
+ORDER BY program.ProgramName
\ No newline at end of file
@@ -0,0 +1,4 @@
+SELECT
+    ISNULL(SUM(QtyProgram), 0) AS Qty
+FROM PartArchive
+WHERE PartName=@part
\ No newline at end of file
@@ -6,6 +6,11 @@ SELECT @ThisWeekSunday = DATEADD(wk, DATEDIFF(wk, 0, GETDATE()), -1)
 SELECT
     REPLACE(PartName, '_', '-') AS Part,
     part.ProgramName AS Program,
+    part.RepeatID,
+    part.ArchivePacketID,
+    part.ArcDateTime,
+    program.MachineName AS Machine,
+    stock.SheetName AS Sheet,
     QtyProgram AS Qty,
     NestedArea * QtyProgram AS Area,
 
@@ -24,4 +29,5 @@ FROM PartArchive AS part
         ON part.ArchivePacketID=program.ArchivePacketID
         AND program.TransType='SN102'
 WHERE part.ArcDateTime >= @PrevWeekSunday
-AND part.ArcDateTime < @ThisWeekSunday
\ No newline at end of file
+AND part.ArcDateTime < @ThisWeekSunday
+ORDER BY part.ArcDateTime
\ No newline at end of file
@@ -0,0 +1,24 @@
+SELECT
+    burned.Part,
+    burned.Wbs,
+    burned.Plant,
+    SUM(burned.QtyProgram) AS Qty
+FROM (
+    SELECT
+        REPLACE(part.PartName, '_', '-') AS Part,
+        NULLIF(stock.Mill,'') AS Wbs,
+        part.QtyProgram,
+
+        CASE LEFT(program.MachineName,7)
+            WHEN 'Plant_3' THEN 'HS02'
+            ELSE 'HS01'
+        END AS Plant
+    FROM PartArchive AS part
+        INNER JOIN StockArchive AS stock
+            ON part.ArchivePacketID=stock.ArchivePacketID
+        INNER JOIN ProgArchive AS program
+            ON part.ArchivePacketID=program.ArchivePacketID
+            AND program.TransType='SN102'
+) AS burned
+GROUP BY burned.Part, burned.Wbs, burned.Plant
+ORDER BY burned.Part
\ No newline at end of file
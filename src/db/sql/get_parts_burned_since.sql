@@ -0,0 +1,28 @@
+SELECT
+    REPLACE(PartName, '_', '-') AS Part,
+    part.ProgramName AS Program,
+    part.RepeatID,
+    part.ArchivePacketID,
+    part.ArcDateTime,
+    program.MachineName AS Machine,
+    stock.SheetName AS Sheet,
+    QtyProgram AS Qty,
+    NestedArea * QtyProgram AS Area,
+
+    stock.Location,
+    stock.PrimeCode AS MaterialMaster,
+    NULLIF(stock.Mill,'') AS Wbs,
+    
+    CASE LEFT(program.MachineName,7)
+        WHEN 'Plant_3' THEN 'HS02'
+        ELSE 'HS01'
+    END AS Plant
+FROM PartArchive AS part
+    INNER JOIN StockArchive AS stock
+        ON part.ArchivePacketID=stock.ArchivePacketID
+    INNER JOIN ProgArchive AS program
+        ON part.ArchivePacketID=program.ArchivePacketID
+        AND program.TransType='SN102'
+WHERE part.ArchivePacketID > @packet_id
+AND part.ArcDateTime >= @archived
+ORDER BY part.ArchivePacketID
\ No newline at end of file
@@ -0,0 +1,35 @@
+SELECT
+    program.ProgramName AS Program,
+    program.RepeatID,
+    program.MachineName AS Machine,
+    program.ArcDateTime,
+
+    stock.SheetName AS Sheet,
+    stock.Location,
+    stock.PrimeCode AS MaterialMaster,
+    NULLIF(stock.Mill,'') AS Wbs,
+    stock.Area,
+
+    parts.Qty,
+    parts.NestedArea,
+
+    CASE LEFT(program.MachineName,7)
+        WHEN 'Plant_3' THEN 'HS02'
+        ELSE 'HS01'
+    END AS Plant
+FROM ProgArchive AS program
+    INNER JOIN StockArchive AS stock
+        ON program.ArchivePacketID=stock.ArchivePacketID
+    INNER JOIN (
+        SELECT
+            ArchivePacketID,
+            SUM(QtyProgram) AS Qty,
+            SUM(NestedArea * QtyProgram) AS NestedArea
+        FROM PartArchive
+        GROUP BY ArchivePacketID
+    ) AS parts
+        ON program.ArchivePacketID=parts.ArchivePacketID
+WHERE program.TransType='SN102'
+AND program.ArcDateTime >= @from
+AND program.ArcDateTime < @to
+ORDER BY program.ArcDateTime
\ No newline at end of file
@@ -0,0 +1,24 @@
+SELECT
+    remnant.SheetName AS Remnant,
+    remnant.ProgramName AS Program,
+    remnant.RepeatID,
+
+    remnant.Location,
+    remnant.PrimeCode AS MaterialMaster,
+    NULLIF(remnant.Mill,'') AS Wbs,
+    remnant.Area,
+
+    CASE LEFT(program.MachineName,7)
+        WHEN 'Plant_3' THEN 'HS02'
+        ELSE 'HS01'
+    END AS Plant
+FROM StockArchive AS remnant
+    INNER JOIN ProgArchive AS program
+        ON remnant.ProgramName=program.ProgramName
+        AND remnant.RepeatID=program.RepeatID
+        AND program.TransType='SN102'
+        AND remnant.ArchivePacketID<>program.ArchivePacketID
+WHERE remnant.SheetType=1
+AND program.ArcDateTime >= @from
+AND program.ArcDateTime < @to
+ORDER BY remnant.ProgramName, remnant.RepeatID
\ No newline at end of file
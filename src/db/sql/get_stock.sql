@@ -0,0 +1,24 @@
+SELECT
+    stock.SheetName AS Sheet,
+    stock.PrimeCode AS MaterialMaster,
+    NULLIF(stock.Mill,'') AS Wbs,
+    stock.Location,
+    stock.Length,
+    stock.Width,
+    stock.Qty,
+    stock.Area * stock.Qty AS Area,
+    CAST(
+        CASE stock.SheetType
+            WHEN 1 THEN 1
+            ELSE 0
+        END
+    AS bit) AS Remnant,
+
+    CASE LEFT(stock.SheetName,1)
+        WHEN 'S' THEN 'HS01'
+        WHEN 'X' THEN 'HS01'
+        WHEN 'C' THEN 'HS01'
+        ELSE 'HS02'
+    END AS Plant
+FROM Stock AS stock
+ORDER BY stock.PrimeCode, stock.SheetName
\ No newline at end of file
@@ -0,0 +1,110 @@
+
+//! crate error type
+
+use std::fmt::Display;
+
+use crate::api::Qty;
+
+/// Result type for sap-watch operations
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors produced by sap-watch
+#[derive(Debug)]
+pub enum Error {
+    /// A value could not be parsed or converted
+    Parse {
+        /// the kind of value being parsed (plant, WBS, order type, etc.)
+        kind: &'static str,
+        /// the value that failed to parse
+        value: String,
+    },
+    /// An operation is not valid for the kind of WBS element
+    WbsOperation {
+        /// the operation attempted
+        operation: &'static str,
+        /// the WBS element it was attempted on, with its kind
+        wbs: String,
+    },
+    /// A database operation failed
+    Db {
+        /// what was being done when the error occurred
+        context: String,
+        /// the underlying database error
+        source: tiberius::error::Error,
+    },
+    /// A query was run with the wrong parameters or returned unexpected columns
+    Query(String),
+    /// A database operation did not complete in time
+    Timeout(String),
+    /// A file operation failed
+    Io {
+        /// what was being done when the error occurred
+        context: String,
+        /// the underlying io error
+        source: std::io::Error,
+    },
+    /// Missing or invalid configuration
+    Config(String),
+    /// An excel file could not be read
+    Excel(String),
+    /// A notification could not be sent
+    Notify(String),
+    /// A quantity could not be allocated
+    Allocation {
+        /// the order being allocated against
+        order: u32,
+        /// quantity requested
+        requested: Qty,
+        /// quantity available
+        available: Qty,
+    },
+}
+
+impl Error {
+    /// creates a [`Error::Parse`] for a kind of value
+    pub fn parse(kind: &'static str, value: impl ToString) -> Self {
+        Self::Parse { kind, value: value.to_string() }
+    }
+
+    /// wraps an io error with the context it occurred in
+    pub fn io(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> Self {
+        let context = context.into();
+        move |source| Self::Io { context, source }
+    }
+
+    /// wraps a database error with the context it occurred in
+    pub fn db<E>(context: impl Into<String>) -> impl FnOnce(E) -> Self
+        where E: Into<tiberius::error::Error>
+    {
+        let context = context.into();
+        move |source| Self::Db { context, source: source.into() }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse { kind, value }                    => write!(f, "Failed to parse {} <{}>", kind, value),
+            Self::WbsOperation { operation, wbs }          => write!(f, "Cannot {} {}", operation, wbs),
+            Self::Db { context, source }                   => write!(f, "Database error while {}: {}", context, source),
+            Self::Query(msg)                               => write!(f, "Query error: {}", msg),
+            Self::Timeout(context)                         => write!(f, "Timed out while {}", context),
+            Self::Io { context, source }                   => write!(f, "IO error while {}: {}", context, source),
+            Self::Config(msg)                              => write!(f, "Configuration error: {}", msg),
+            Self::Excel(msg)                               => write!(f, "Excel error: {}", msg),
+            Self::Notify(msg)                              => write!(f, "Notification error: {}", msg),
+            Self::Allocation { order, requested, available } =>
+                write!(f, "Cannot apply qty({}) greater than order {}({})", requested, order, available),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Db { source, .. } => Some(source),
+            Self::Io { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
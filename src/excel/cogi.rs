@@ -0,0 +1,112 @@
+
+//! parsing for SAP transaction COGI (postprocessing of goods movement errors)
+
+use calamine::DataType;
+
+use std::{collections::HashMap, fmt::Display};
+use std::path::PathBuf;
+
+use crate::api::{CogiError, Quantity, Uom};
+use crate::{Error, Result};
+use super::excel::{split_errors, XlsxTableReader, Header, get_date};
+
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+enum CogiHeader {
+    Matl,
+    Plant,
+    Loc,
+    Wbs,
+    Qty,
+    Uom,
+    Message,
+    Date,
+}
+
+impl Header for CogiHeader {
+    type Row = CogiError;
+
+    fn columns_to_match() -> Vec<Self> where Self: Sized {
+        vec![
+            CogiHeader::Matl,
+            CogiHeader::Plant,
+            CogiHeader::Loc,
+            CogiHeader::Wbs,
+            CogiHeader::Qty,
+            CogiHeader::Uom,
+            CogiHeader::Message,
+            CogiHeader::Date,
+        ]
+    }
+
+    fn match_header_column(column_text: &str) -> Option<Self>
+        where Self: Sized
+    {
+        match column_text {
+            "Material"                                    => Some( Self::Matl    ),
+            "Plant"                                       => Some( Self::Plant   ),
+            "Storage Location"                            => Some( Self::Loc     ),
+            "WBS Element" | "Special stock number"        => Some( Self::Wbs     ),
+            "Quantity" | "Qty in unit of entry"           => Some( Self::Qty     ),
+            "Unit of Entry" | "Base Unit of Measure"      => Some( Self::Uom     ),
+            "Message Text" | "Message text"               => Some( Self::Message ),
+            "Date of Error" | "Posting Date" | "Created on" => Some( Self::Date  ),
+            _                                             => None
+        }
+    }
+
+    fn parse_row(header: &HashMap<Self, usize>, row: &[DataType]) -> Result<Self::Row>
+        where Self: Sized
+    {
+        let matl    = row[*header.get(&Self::Matl).unwrap()   ].get_string().ok_or( Error::Excel("Failed to read Material as String".into()) )?.into();
+        let plant   = row[*header.get(&Self::Plant).unwrap()  ].get_string().ok_or( Error::Excel("Failed to read Plant".into()) )?.try_into()?;
+        let loc     = row[*header.get(&Self::Loc).unwrap()    ].get_string().unwrap_or_default().into();
+        let wbs     = row[*header.get(&Self::Wbs).unwrap()    ].get_string().unwrap_or_default().try_into()?;
+        let qty     = row[*header.get(&Self::Qty).unwrap()    ].as_f64()    .ok_or( Error::Excel("Failed to read qty as Float".into()) )?;
+        let uom     = row[*header.get(&Self::Uom).unwrap()    ].get_string().ok_or( Error::Excel("Failed to read Unit of Measure".into()) )?;
+        let qty     = Quantity::new(qty, Uom::try_from(uom)?);
+        let message = row[*header.get(&Self::Message).unwrap()].get_string().ok_or( Error::Excel("Failed to read Message Text".into()) )?.into();
+        let date    = get_date( &row[*header.get(&Self::Date).unwrap()] );
+
+        Ok( CogiError { matl, plant, loc, wbs, qty, message, date } )
+    }
+}
+
+/// parses a COGI excel file from a given export file path
+pub fn parse_cogi_xl(cogi_file: PathBuf) -> crate::Result<Vec<CogiError>> {
+
+    let mut reader = XlsxTableReader::<CogiHeader>::new();
+    let vals = reader.read_file(cogi_file)?
+        .into_iter()
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(vals)
+}
+
+/// parses a COGI excel file, also returning a message for each row that failed to parse
+pub fn parse_cogi_xl_with_errors(cogi_file: PathBuf) -> crate::Result<(Vec<CogiError>, Vec<String>)> {
+    let mut reader = XlsxTableReader::<CogiHeader>::new();
+    let rows = reader.read_file(cogi_file)?;
+
+    Ok( split_errors("COGI", rows) )
+}
+
+impl Display for CogiHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use CogiHeader::*;
+
+        let name = match self {
+            Matl => "Material",
+            Plant => "Plant",
+            Loc => "Storage Location",
+            Wbs => "WBS Element",
+            Qty => "Quantity",
+            Uom => "Unit of Entry",
+            Message => "Message Text",
+            Date => "Date",
+        };
+
+        write!(f, "{}", name)
+    }
+}
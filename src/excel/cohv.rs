@@ -8,8 +8,9 @@ use calamine::DataType;
 use std::{collections::HashMap, fmt::Display};
 use std::path::PathBuf;
 
-use crate::api::{Order, OrderData};
-use super::excel::{XlsxTableReader, Header};
+use crate::api::{Order, OrderData, Qty};
+use crate::{Error, Result};
+use super::excel::{split_errors, XlsxTableReader, Header};
 
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -50,26 +51,28 @@ impl Header for CohvHeader {
         }
     }
 
-    fn parse_row(header: &HashMap<Self, usize>, row: &[DataType]) -> anyhow::Result<Self::Row>
+    fn parse_row(header: &HashMap<Self, usize>, row: &[DataType]) -> Result<Self::Row>
         where Self: Sized
     {
         // TODO: handle parsing errors (get_string/get_int)
-        let order = row[*header.get(&Self::Order).unwrap()].get_string().ok_or( anyhow!("Failed to coerce order to String") )?.parse()?;
+        let order = row[*header.get(&Self::Order).unwrap()].get_string().ok_or( Error::Excel("Failed to coerce order to String".into()) )?;
+        let order = order.parse().map_err(|_| Error::parse("order number", order))?;
 
-        let matl  = row[*header.get(&Self::Matl).unwrap() ].get_string().ok_or( anyhow!("Failed to read Material as String") )?.into();
-        let qty   = row[*header.get(&Self::Qty).unwrap()  ].get_float() .ok_or( anyhow!("Failed to read qty as Float") )? as u32;
-        let wbs   = row[*header.get(&Self::Wbs).unwrap()  ].get_string().ok_or( anyhow!("Failed to read Wbs Element") )?.try_into()?;
-        let _type = row[*header.get(&Self::Type).unwrap() ].get_string().ok_or( anyhow!("Failed to read Order Type") )?;
-        let plant = row[*header.get(&Self::Plant).unwrap()].get_string().ok_or( anyhow!("Failed to read Plant") )?.into();
+        let matl  = row[*header.get(&Self::Matl).unwrap() ].get_string().ok_or( Error::Excel("Failed to read Material as String".into()) )?.into();
+        let qty   = row[*header.get(&Self::Qty).unwrap()  ].get_float() .ok_or( Error::Excel("Failed to read qty as Float".into()) )?;
+        let wbs   = row[*header.get(&Self::Wbs).unwrap()  ].get_string().ok_or( Error::Excel("Failed to read Wbs Element".into()) )?.try_into()?;
+        let _type = row[*header.get(&Self::Type).unwrap() ].get_string().ok_or( Error::Excel("Failed to read Order Type".into()) )?;
+        let plant = row[*header.get(&Self::Plant).unwrap()].get_string().ok_or( Error::Excel("Failed to read Plant".into()) )?.try_into()?;
 
+        let qty = Qty::try_from(qty)?;
         let data = OrderData { id: order, mark: matl, qty, wbs, plant };
 
-        Ok( Order::new(_type, data) )
+        Order::new(_type, data)
     }
 }
 
 /// parses a COHV excel file from a given export file path
-pub fn parse_cohv_xl(cohv_file: PathBuf) -> anyhow::Result<Vec<Order>> {
+pub fn parse_cohv_xl(cohv_file: PathBuf) -> crate::Result<Vec<Order>> {
     
     let mut reader = XlsxTableReader::<CohvHeader>::new();
     let vals = reader.read_file(cohv_file)?
@@ -80,6 +83,14 @@ pub fn parse_cohv_xl(cohv_file: PathBuf) -> anyhow::Result<Vec<Order>> {
     Ok(vals)
 }
 
+/// parses a COHV excel file, also returning a message for each row that failed to parse
+pub fn parse_cohv_xl_with_errors(cohv_file: PathBuf) -> crate::Result<(Vec<Order>, Vec<String>)> {
+    let mut reader = XlsxTableReader::<CohvHeader>::new();
+    let rows = reader.read_file(cohv_file)?;
+
+    Ok( split_errors("COHV", rows) )
+}
+
 impl Display for CohvHeader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use CohvHeader::*;
@@ -10,6 +10,9 @@ use std::{
 
 use itertools::Itertools;
 use calamine::{Reader, open_workbook, Xlsx, DataType};
+use time::{Date, Duration, Month};
+
+use crate::{Error, Result};
 
 // TODO: use serde for this.
 
@@ -35,7 +38,7 @@ impl<H> XlsxTableReader<H>
     // TODO: support multiple row headers
     pub fn parse_header(&mut self, row: &[DataType]) {
         for (i, col) in row.iter().enumerate() {
-            if let Some(key) = H::match_header_column(col.get_string().unwrap()) {
+            if let Some(key) = col.get_string().and_then(H::match_header_column) {
                 self.header.insert(key, i);
             }
 
@@ -67,19 +70,26 @@ impl<H> XlsxTableReader<H>
     }
 
     /// read an excel file, parsing the header and returning the parsed rows
-    pub fn read_file(&mut self, path: PathBuf) -> anyhow::Result<Vec<anyhow::Result<H::Row>>> {
-        let mut wb: Xlsx<_> = match open_workbook(path) {
+    pub fn read_file(&mut self, path: PathBuf) -> Result<Vec<Result<H::Row>>> {
+        let mut wb: Xlsx<_> = match open_workbook(&path) {
             Ok(wb) => wb,
-            Err(_) => return Err( anyhow!("failed ot open file") )
+            Err(e) => return Err( Error::Excel(format!("failed to open file `{}`: {}", path.display(), e)) )
         };
         
-        let rng = &wb.worksheets()[0].1;
-        let mut rows = rng.rows().into_iter();
+        let sheets = wb.worksheets();
+        let rng = match sheets.first() {
+            Some((_, rng)) => rng,
+            None => return Err( Error::Excel(format!("no worksheets in `{}`", path.display())) )
+        };
+        let mut rows = rng.rows();
 
-        self.parse_header(rows.next().unwrap());
+        match rows.next() {
+            Some(header) => self.parse_header(header),
+            None => return Err( Error::Excel(format!("no header row in `{}`", path.display())) )
+        }
 
         if self.is_header_matched() {
-            return Err( anyhow!("Header did not match all columns: `{}`", self.missing_columns().join(", ")) );
+            return Err( Error::Excel(format!("Header did not match all columns: `{}`", self.missing_columns().join(", "))) );
         }
 
         let mut results = Vec::new();
@@ -87,12 +97,25 @@ impl<H> XlsxTableReader<H>
             results.push(H::parse_row(&self.header, row));
         }
 
-        return Ok(results);
+        Ok(results)
 
         // Err(String::from("Failed to open first worksheet"))
     }
 }
 
+/// splits the rows read by [`XlsxTableReader::read_file`] into the parsed rows
+/// and a message for each row that failed to parse
+pub fn split_errors<T>(file: &str, rows: Vec<Result<T>>) -> (Vec<T>, Vec<String>) {
+    rows
+        .into_iter()
+        .enumerate()
+        // the header is row 1
+        .partition_map(|(i, row)| match row {
+            Ok(row) => itertools::Either::Left(row),
+            Err(e) => itertools::Either::Right(format!("{} row {}: {}", file, i + 2, e)),
+        })
+}
+
 /// Header parser to aid the sheet parser
 pub trait Header {
     /// type of Row that is returned by the parser during `read_file`
@@ -103,5 +126,40 @@ pub trait Header {
     /// get a list of the columns to match in the header
     fn columns_to_match() -> Vec<Self> where Self: Sized;
     /// parse a data row with the parsed colum
-    fn parse_row(header: &HashMap<Self, usize>, row: &[DataType]) -> anyhow::Result<Self::Row> where Self: Sized;
-}
\ No newline at end of file
+    fn parse_row(header: &HashMap<Self, usize>, row: &[DataType]) -> Result<Self::Row> where Self: Sized;
+}
+
+/// reads a cell as a date
+/// 
+/// Handles excel serial dates as well as the text formats SAP exports
+/// (`MM/DD/YYYY`, `DD.MM.YYYY` and `YYYY-MM-DD`)
+pub fn get_date(cell: &DataType) -> Option<Date> {
+    match cell {
+        DataType::DateTime(serial) | DataType::Float(serial) => {
+            // excel serial dates count days from 1899-12-30
+            let epoch = Date::from_calendar_date(1899, Month::December, 30).ok()?;
+            epoch.checked_add( Duration::days(serial.trunc() as i64) )
+        },
+        DataType::Int(serial) => get_date(&DataType::Float(*serial as f64)),
+        DataType::String(text) | DataType::DateTimeIso(text) => parse_date_str(text),
+        _ => None
+    }
+}
+
+fn parse_date_str(text: &str) -> Option<Date> {
+    // drop any time component
+    let text = text.split(['T', ' ']).next()?;
+
+    let parts: Vec<&str> = text.split(['/', '.', '-']).collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let (year, month, day) =
+        if text.contains('/')      { (parts[2], parts[0], parts[1]) }
+        else if text.contains('.') { (parts[2], parts[1], parts[0]) }
+        else                       { (parts[0], parts[1], parts[2]) };
+
+    let month = Month::try_from(month.parse::<u8>().ok()?).ok()?;
+    Date::from_calendar_date(year.parse().ok()?, month, day.parse().ok()?).ok()
+}
@@ -0,0 +1,115 @@
+
+//! parsing for SAP transaction MB51 (material document list)
+
+use calamine::DataType;
+use ftlog::warn;
+
+use std::{collections::HashMap, fmt::Display};
+use std::path::PathBuf;
+
+use crate::api::{MaterialDocument, MovementType, Quantity, Uom};
+use crate::{Error, Result};
+use super::excel::{XlsxTableReader, Header, get_date, split_errors};
+
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+enum Mb51Header {
+    Movement,
+    Matl,
+    Plant,
+    Wbs,
+    Qty,
+    Uom,
+    Date,
+    Reference,
+}
+
+impl Header for Mb51Header {
+    /// `None` for movement types that are not confirmations (see [`MovementType`])
+    type Row = Option<MaterialDocument>;
+
+    fn columns_to_match() -> Vec<Self> where Self: Sized {
+        vec![
+            Mb51Header::Movement,
+            Mb51Header::Matl,
+            Mb51Header::Plant,
+            Mb51Header::Wbs,
+            Mb51Header::Qty,
+            Mb51Header::Uom,
+            Mb51Header::Date,
+            Mb51Header::Reference,
+        ]
+    }
+
+    fn match_header_column(column_text: &str) -> Option<Self>
+        where Self: Sized
+    {
+        match column_text {
+            "Movement Type" | "Movement type"               => Some( Self::Movement  ),
+            "Material"                                      => Some( Self::Matl      ),
+            "Plant"                                         => Some( Self::Plant     ),
+            "WBS Element" | "Special stock number"          => Some( Self::Wbs       ),
+            "Quantity" | "Qty in unit of entry"             => Some( Self::Qty       ),
+            "Unit of Entry" | "Base Unit of Measure"        => Some( Self::Uom       ),
+            "Posting Date"                                  => Some( Self::Date      ),
+            "Reference" | "Document Header Text"            => Some( Self::Reference ),
+            _                                               => None
+        }
+    }
+
+    fn parse_row(header: &HashMap<Self, usize>, row: &[DataType]) -> Result<Self::Row>
+        where Self: Sized
+    {
+        let movement  = row[*header.get(&Self::Movement).unwrap() ].as_string().ok_or( Error::Excel("Failed to read Movement Type".into()) )?;
+        let code      = movement.trim().parse::<u32>().map_err(|_| Error::parse("movement type", movement))?;
+        let movement  = match MovementType::try_from(code) {
+            Ok(movement) => movement,
+            Err(_) => return Ok(None)
+        };
+
+        let matl      = row[*header.get(&Self::Matl).unwrap()     ].get_string().ok_or( Error::Excel("Failed to read Material as String".into()) )?.into();
+        let plant     = row[*header.get(&Self::Plant).unwrap()    ].get_string().ok_or( Error::Excel("Failed to read Plant".into()) )?.try_into()?;
+        let wbs       = row[*header.get(&Self::Wbs).unwrap()      ].get_string().unwrap_or_default().try_into()?;
+        let qty       = row[*header.get(&Self::Qty).unwrap()      ].as_f64()    .ok_or( Error::Excel("Failed to read qty as Float".into()) )?.abs();
+        let uom       = row[*header.get(&Self::Uom).unwrap()      ].get_string().ok_or( Error::Excel("Failed to read Unit of Measure".into()) )?;
+        let qty       = Quantity::new(qty, Uom::try_from(uom)?);
+        let date      = get_date( &row[*header.get(&Self::Date).unwrap()] );
+        let reference = row[*header.get(&Self::Reference).unwrap()].as_string().unwrap_or_default().trim().into();
+
+        Ok( Some(MaterialDocument { movement, matl, plant, wbs, qty, date, reference }) )
+    }
+}
+
+/// parses a MB51 excel file from a given export file path
+///
+/// Lines with movement types other than 101/261/262/531 are skipped. Lines
+/// that fail to parse are logged and skipped.
+pub fn parse_mb51_xl(mb51_file: PathBuf) -> crate::Result<Vec<MaterialDocument>> {
+
+    let mut reader = XlsxTableReader::<Mb51Header>::new();
+    let (docs, errors) = split_errors("MB51", reader.read_file(mb51_file)?);
+    for error in errors {
+        warn!("skipping {}", error);
+    }
+
+    Ok( docs.into_iter().flatten().collect() )
+}
+
+impl Display for Mb51Header {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Mb51Header::*;
+
+        let name = match self {
+            Movement => "Movement Type",
+            Matl => "Material",
+            Plant => "Plant",
+            Wbs => "WBS Element",
+            Qty => "Quantity",
+            Uom => "Unit of Entry",
+            Date => "Posting Date",
+            Reference => "Reference",
+        };
+
+        write!(f, "{}", name)
+    }
+}
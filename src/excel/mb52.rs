@@ -0,0 +1,105 @@
+
+//! parsing for SAP transaction MB52 (warehouse stocks of material)
+//!
+//! MMBE exports can be read as long as they are exported in the same list layout
+
+use calamine::DataType;
+
+use std::{collections::HashMap, fmt::Display};
+use std::path::PathBuf;
+
+use crate::api::{Quantity, StockItem, Uom, Wbs};
+use crate::{Error, Result};
+use super::excel::{XlsxTableReader, Header};
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+enum Mb52Header {
+    Matl,
+    Plant,
+    Loc,
+    SpecialStock,
+    Wbs,
+    Qty,
+    Uom,
+}
+
+impl Header for Mb52Header {
+    type Row = StockItem;
+
+    fn columns_to_match() -> Vec<Self> where Self: Sized {
+        vec![
+            Mb52Header::Matl,
+            Mb52Header::Plant,
+            Mb52Header::Loc,
+            Mb52Header::SpecialStock,
+            Mb52Header::Wbs,
+            Mb52Header::Qty,
+            Mb52Header::Uom,
+        ]
+    }
+
+    fn match_header_column(column_text: &str) -> Option<Self>
+        where Self: Sized
+    {
+        match column_text {
+            "Material"                                 => Some( Self::Matl         ),
+            "Plant"                                    => Some( Self::Plant        ),
+            "Storage Location"                         => Some( Self::Loc          ),
+            "Special Stock"                            => Some( Self::SpecialStock ),
+            "Special stock number" | "WBS Element"     => Some( Self::Wbs          ),
+            "Unrestricted"                             => Some( Self::Qty          ),
+            "Base Unit of Measure" | "Base unit of measure" => Some( Self::Uom     ),
+            _                                          => None
+        }
+    }
+
+    fn parse_row(header: &HashMap<Self, usize>, row: &[DataType]) -> Result<Self::Row>
+        where Self: Sized
+    {
+        let matl  = row[*header.get(&Self::Matl).unwrap() ].get_string().ok_or( Error::Excel("Failed to read Material as String".into()) )?.into();
+        let plant = row[*header.get(&Self::Plant).unwrap()].get_string().ok_or( Error::Excel("Failed to read Plant".into()) )?.try_into()?;
+        let loc   = row[*header.get(&Self::Loc).unwrap()  ].get_string().unwrap_or_default().into();
+        let qty   = row[*header.get(&Self::Qty).unwrap()  ].as_f64()    .ok_or( Error::Excel("Failed to read qty as Float".into()) )?;
+        let uom   = row[*header.get(&Self::Uom).unwrap()  ].get_string().ok_or( Error::Excel("Failed to read Unit of Measure".into()) )?;
+
+        // only project stock is held against a WBS element
+        let wbs = match row[*header.get(&Self::SpecialStock).unwrap()].get_string() {
+            Some("Q") => row[*header.get(&Self::Wbs).unwrap()].get_string().ok_or( Error::Excel("Failed to read Wbs Element".into()) )?.try_into()?,
+            _ => Wbs::None
+        };
+
+        let qty = Quantity::new(qty, Uom::try_from(uom)?).as_area()?;
+
+        Ok( StockItem { matl, plant, loc, wbs, qty } )
+    }
+}
+
+/// parses a MB52 excel file from a given export file path
+pub fn parse_mb52_xl(mb52_file: PathBuf) -> crate::Result<Vec<StockItem>> {
+
+    let mut reader = XlsxTableReader::<Mb52Header>::new();
+    let vals = reader.read_file(mb52_file)?
+        .into_iter()
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(vals)
+}
+
+impl Display for Mb52Header {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Mb52Header::*;
+
+        let name = match self {
+            Matl => "Material",
+            Plant => "Plant",
+            Loc => "Storage Location",
+            SpecialStock => "Special Stock",
+            Wbs => "Special stock number",
+            Qty => "Unrestricted",
+            Uom => "Base Unit of Measure",
+        };
+
+        write!(f, "{}", name)
+    }
+}
@@ -1,5 +1,9 @@
 
 //! excel file parsing
 
+pub mod cogi;
 pub mod cohv;
+#[allow(clippy::module_inception)]
 pub mod excel;
+pub mod mb51;
+pub mod mb52;
@@ -1,14 +1,21 @@
 
-#![feature(lazy_cell)]
-
 #![warn(missing_docs)]
 
 //! SAP error pre-cogi watching and handling
 
-#[macro_use] extern crate anyhow;
 #[macro_use] extern crate serde;
 
 pub mod api;
+pub mod dates;
 pub mod db;
+mod error;
 pub mod excel;
-pub mod logging;
\ No newline at end of file
+pub mod logging;
+pub mod notify;
+pub mod output;
+pub mod recon;
+#[cfg(feature = "http")]
+pub mod server;
+mod state;
+
+pub use error::{Error, Result};
\ No newline at end of file
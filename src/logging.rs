@@ -8,15 +8,21 @@ use ftlog::{
     FtLogFormatter, LevelFilter,
 };
 
+use crate::{Error, Result};
+
 /// initializes the [`ftlog`] logger
 /// 
 /// ['ftlog']: https://docs.rs/ftlog/latest/ftlog/
-pub fn init_logger() {
+pub fn init_logger() -> Result<()> {
 
     let time_format = time::format_description::parse_owned::<1>(
         "[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:6]",
     )
-    .unwrap();
+    .map_err(|e| Error::Config(format!("invalid log time format: {}", e)))?;
+
+    // file appenders panic if the directory does not exist
+    std::fs::create_dir_all("logs")
+        .map_err(|e| Error::Config(format!("failed to create logs directory: {}", e)))?;
 
     // configurate logger
     ftlog::builder()
@@ -49,5 +55,7 @@ pub fn init_logger() {
         .filter("ftlog::appender", "ftlog-appender", LevelFilter::Error)
         .appender("ftlog-appender", FileAppender::new("logs/ftlog-appender.log"))
         .try_init()
-        .expect("logger build or set failed");
+        .map_err(|e| Error::Config(format!("logger build or set failed: {}", e)))?;
+
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,41 @@
+
+use serde::Serialize;
+
+use crate::output::{Severity, Tabular};
+
+/// A critical finding to push as soon as it is found
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// what was found (e.g. "Findings", "COGI risks")
+    pub kind: String,
+    /// the record as `header: value` pairs
+    pub text: String,
+    /// the record itself
+    pub record: serde_json::Value,
+}
+
+impl Alert {
+    /// alerts for the records that need to be fixed ([`Severity::Error`])
+    pub fn critical<'a, T: Tabular + Serialize + 'a>(kind: &str, records: impl IntoIterator<Item = &'a T>) -> Vec<Self> {
+        records
+            .into_iter()
+            .filter(|record| record.severity() == Some(Severity::Error))
+            .map(|record| Self {
+                kind: kind.into(),
+                text: T::header()
+                    .iter()
+                    .zip(record.row())
+                    .filter(|(_, val)| !val.is_empty())
+                    .map(|(col, val)| format!("{}: {}", col, val))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                record: serde_json::to_value(record).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// identifies repeats of the alert
+    pub fn key(&self) -> String {
+        format!("{}: {}", self.kind, self.text)
+    }
+}
@@ -0,0 +1,255 @@
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::api::Plant;
+use crate::output::{Severity, Tabular};
+use crate::recon::{Finding, RootCause};
+use crate::{state, Result};
+
+/// A row of a digest section
+#[derive(Debug, Clone)]
+struct Item {
+    /// the plant the row is for, `None` if it is for every plant
+    plant: Option<Plant>,
+    row: Vec<String>,
+    severity: Option<Severity>,
+    /// identifies the row in later digests, `None` if it is reported every time
+    key: Option<String>,
+}
+
+/// A table of a digest
+#[derive(Debug, Clone)]
+struct Section {
+    title: String,
+    header: Vec<&'static str>,
+    items: Vec<Item>,
+}
+
+/// The rows reported by earlier digests, by key
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Reported {
+    reported: BTreeSet<String>,
+}
+
+impl Reported {
+    /// loads the rows reported, none if the file does not exist yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        state::load_or_default(path.as_ref(), "reported digest rows")
+    }
+
+    /// saves the rows reported
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        state::save(self, path.as_ref(), "reported digest rows")
+    }
+}
+
+/// A summary of findings to send as a notification
+#[derive(Debug, Clone)]
+pub struct Digest {
+    /// the subject of the digest
+    pub title: String,
+    sections: Vec<Section>,
+    findings: Vec<Finding>,
+}
+
+impl Digest {
+    /// creates an empty digest
+    pub fn new(title: impl Into<String>) -> Self {
+        Self { title: title.into(), sections: Vec::new(), findings: Vec::new() }
+    }
+
+    /// adds a section of records, with the plant each record is for
+    pub fn section<'a, T: Tabular + 'a>(
+        &mut self,
+        title: impl Into<String>,
+        records: impl IntoIterator<Item = &'a T>,
+        plant: impl Fn(&T) -> Option<Plant>,
+    ) -> &mut Self {
+        let title = title.into();
+        let items: Vec<Item> = records
+            .into_iter()
+            .map(|record| Item {
+                plant: plant(record),
+                key: Some(key(&title, &record.row())),
+                row: record.row(),
+                severity: record.severity(),
+            })
+            .collect();
+
+        if !items.is_empty() {
+            self.sections.push(Section { title, header: T::header(), items });
+        }
+
+        self
+    }
+
+    /// adds reconciliation findings, which are also attached as the excel report
+    pub fn findings(&mut self, findings: &[Finding]) -> &mut Self {
+        self.findings.extend_from_slice(findings);
+
+        self.section("Findings", findings, Finding::plant)
+    }
+
+    /// adds COGI errors and the burns behind them
+    pub fn cogi(&mut self, causes: &[RootCause]) -> &mut Self {
+        self.section("COGI risks", causes, |cause| Some(cause.error.plant.clone()))
+    }
+
+    /// adds errors met while gathering the digest (e.g. export rows that failed to parse)
+    pub fn errors(&mut self, errors: &[String]) -> &mut Self {
+        let items: Vec<Item> = errors
+            .iter()
+            .map(|error| Item { plant: None, row: vec![error.clone()], severity: Some(Severity::Error), key: None })
+            .collect();
+
+        if !items.is_empty() {
+            self.sections.push(Section { title: "Errors".into(), header: vec!["Error"], items });
+        }
+
+        self
+    }
+
+    /// drops the rows reported by earlier digests
+    ///
+    /// Returns the rows reported once this digest is sent. Rows that are no
+    /// longer found are dropped from it, so they are reported again if they
+    /// come back. Errors are reported every time.
+    pub fn skip_reported(&mut self, reported: &Reported) -> Reported {
+        let found: BTreeSet<String> = self.sections
+            .iter()
+            .flat_map(|section| &section.items)
+            .filter_map(|item| item.key.clone())
+            .collect();
+
+        let is_new = |key: Option<&String>| key.is_none_or(|key| !reported.reported.contains(key));
+        for section in &mut self.sections {
+            section.items.retain(|item| is_new(item.key.as_ref()));
+        }
+        self.sections.retain(|section| !section.items.is_empty());
+        self.findings.retain(|finding| is_new(Some(&key("Findings", &finding.row()))));
+
+        Reported { reported: found }
+    }
+
+    /// if there is nothing to report
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// the findings to attach as the excel report
+    pub fn attached_findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// the part of the digest for a plant, including what is not for any plant
+    pub fn for_plant(&self, plant: &Plant) -> Self {
+        let for_plant = |item_plant: &Option<Plant>| item_plant.as_ref().is_none_or(|p| p == plant);
+
+        Self {
+            title: format!("{} ({})", self.title, plant),
+            sections: self.sections
+                .iter()
+                .map(|section| Section {
+                    items: section.items.iter().filter(|item| for_plant(&item.plant)).cloned().collect(),
+                    ..section.clone()
+                })
+                .filter(|section| !section.items.is_empty())
+                .collect(),
+            findings: self.findings.iter().filter(|finding| for_plant(&finding.plant())).cloned().collect(),
+        }
+    }
+
+    /// a plain text summary of the digest
+    pub fn summary(&self) -> String {
+        self.sections
+            .iter()
+            .map(|section| format!("{}: {}", section.title, section.items.len()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// the digest as an html document
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<html>\n<body style=\"font-family: Calibri, Arial, sans-serif; font-size: 11pt\">\n");
+
+        // writing to a String does not fail
+        let _ = writeln!(html, "<h2>{}</h2>", escape(&self.title));
+
+        for section in &self.sections {
+            let _ = writeln!(html, "<h3>{} ({})</h3>", escape(&section.title), section.items.len());
+            html.push_str("<table border=\"1\" cellspacing=\"0\" cellpadding=\"4\" style=\"border-collapse: collapse\">\n");
+
+            let header: String = section.header.iter().map(|col| format!("<th>{}</th>", escape(col))).collect();
+            let _ = writeln!(html, "<tr style=\"background-color: #d9d9d9\">{}</tr>", header);
+
+            for item in &section.items {
+                let style = match item.severity {
+                    Some(Severity::Error)   => " style=\"background-color: #f8d7da\"",
+                    Some(Severity::Warning) => " style=\"background-color: #fff3cd\"",
+                    Some(Severity::Ok)      => " style=\"background-color: #d4edda\"",
+                    None                    => "",
+                };
+                let row: String = item.row.iter().map(|val| format!("<td>{}</td>", escape(val))).collect();
+                let _ = writeln!(html, "<tr{}>{}</tr>", style, row);
+            }
+
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn key(title: &str, row: &[String]) -> String {
+    format!("{}: {}", title, row.join(", "))
+}
+
+fn escape(text: &str) -> String {
+    text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::{Qty, Wbs};
+
+    fn digest(marks: &[&str]) -> Digest {
+        let findings: Vec<Finding> = marks
+            .iter()
+            .map(|mark| Finding::UnmatchedBurn {
+                mark: mark.to_string(), program: "12345".into(), plant: "HS01".into(), matl_wbs: Wbs::None, qty: Qty(2)
+            })
+            .collect();
+
+        let mut digest = Digest::new("digest");
+        digest.findings(&findings).errors(&["row 2: invalid qty".into()]);
+        digest
+    }
+
+    #[test]
+    fn only_rows_not_reported_before_are_reported() {
+        let mut first = digest(&["1200001A-X1", "1200001A-X2"]);
+        let reported = first.skip_reported(&Reported::default());
+        assert_eq!(first.summary(), "Findings: 2, Errors: 1");
+
+        let mut second = digest(&["1200001A-X2", "1200001A-X3"]);
+        let reported = second.skip_reported(&reported);
+        assert_eq!(second.summary(), "Findings: 1, Errors: 1");
+        assert_eq!(second.attached_findings()[0].mark(), "1200001A-X3");
+
+        // X1 was resolved, so it is reported again when it comes back
+        let mut third = digest(&["1200001A-X1", "1200001A-X3"]);
+        third.skip_reported(&reported);
+        assert_eq!(third.summary(), "Findings: 1, Errors: 1");
+        assert_eq!(third.attached_findings()[0].mark(), "1200001A-X1");
+    }
+}
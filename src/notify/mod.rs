@@ -0,0 +1,14 @@
+
+//! notifications of new findings
+
+mod alert;
+pub use alert::Alert;
+
+mod digest;
+pub use digest::{Digest, Reported};
+
+pub mod smtp;
+pub use smtp::SmtpNotifier;
+
+pub mod webhook;
+pub use webhook::WebhookNotifier;
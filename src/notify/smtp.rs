@@ -0,0 +1,294 @@
+
+//! email digests over SMTP
+
+use ftlog::info;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::Digest;
+use crate::api::Plant;
+use crate::db::config::{required, var};
+use crate::output::xlsx;
+use crate::{Error, Result};
+
+const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tls {
+    /// plain text (for a local relay or test server)
+    None,
+    /// upgrade a plain connection with `STARTTLS`
+    StartTls,
+    /// TLS from the start (SMTPS)
+    Tls,
+}
+
+/// SMTP notifier configuration
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    /// server host
+    pub host: String,
+    /// server port, if not the default for the TLS mode
+    pub port: Option<u16>,
+    /// how the connection is secured
+    pub tls: Tls,
+    /// login (user and password), if the server requires one
+    pub credentials: Option<(String, String)>,
+    /// sender address
+    pub from: Mailbox,
+    /// recipients of the whole digest
+    pub to: Vec<Mailbox>,
+    /// recipients of the part of the digest for a plant
+    pub plant_to: Vec<(Plant, Vec<Mailbox>)>,
+}
+
+impl SmtpConfig {
+    /// builds the SMTP config from the environment
+    ///
+    /// | variable                      | use                                                 |
+    /// |-------------------------------|-----------------------------------------------------|
+    /// | `SMTP_HOST`                   | server host                                         |
+    /// | `SMTP_PORT`                   | server port (default for the TLS mode)              |
+    /// | `SMTP_TLS`                    | `starttls` (default), `tls` or `none`               |
+    /// | `SMTP_USER`/`SMTP_PWD`        | login, if the server requires one                   |
+    /// | `SMTP_FROM`                   | sender address                                      |
+    /// | `SMTP_TO`                     | recipients of the whole digest (comma separated)    |
+    /// | `SMTP_TO_HS01`/`SMTP_TO_HS02` | recipients of a plant's findings (comma separated)  |
+    pub fn from_env() -> Result<Self> {
+        let port = match var("SMTP_PORT") {
+            Some(port) => Some( port.parse().map_err(|_| Error::Config(format!("invalid `SMTP_PORT` <{}>", port)))? ),
+            None => None
+        };
+
+        let tls = match var("SMTP_TLS").as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("starttls") => Tls::StartTls,
+            Some("tls")             => Tls::Tls,
+            Some("none")            => Tls::None,
+            Some(other) => return Err( Error::Config(format!("invalid `SMTP_TLS` <{}>", other)) )
+        };
+
+        let credentials = match var("SMTP_USER") {
+            Some(user) => Some( (user, required("SMTP_PWD")?) ),
+            None => None
+        };
+
+        let mut plant_to = Vec::new();
+        for plant in [Plant::Lancaster, Plant::Williamsport] {
+            let name = format!("SMTP_TO_{}", plant);
+            if let Some(to) = var(&name) {
+                plant_to.push( (plant, mailboxes(&name, &to)?) );
+            }
+        }
+
+        let to = match var("SMTP_TO") {
+            Some(to) => mailboxes("SMTP_TO", &to)?,
+            None => Vec::new()
+        };
+        if to.is_empty() && plant_to.is_empty() {
+            return Err( Error::Config("no recipients defined (`SMTP_TO` or `SMTP_TO_<plant>`)".into()) );
+        }
+
+        Ok(Self {
+            host: required("SMTP_HOST")?,
+            port,
+            tls,
+            credentials,
+            from: mailboxes("SMTP_FROM", &required("SMTP_FROM")?)?
+                .pop()
+                .ok_or_else(|| Error::Config("`SMTP_FROM` is empty".into()))?,
+            to,
+            plant_to,
+        })
+    }
+}
+
+fn mailboxes(name: &str, list: &str) -> Result<Vec<Mailbox>> {
+    list
+        .split(',')
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .map(|addr| addr.parse().map_err(|e| Error::Config(format!("invalid address <{}> in `{}`: {}", addr, name, e))))
+        .collect()
+}
+
+/// Sends [`Digest`]s by email, with the findings attached as an excel report
+#[derive(Clone)]
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    plant_to: Vec<(Plant, Vec<Mailbox>)>,
+}
+
+impl SmtpNotifier {
+    /// creates a notifier, configured from the environment
+    ///
+    /// see [`SmtpConfig::from_env`] for the variables used
+    pub fn from_env() -> Result<Self> {
+        Self::new(SmtpConfig::from_env()?)
+    }
+
+    /// creates a notifier with a given config
+    pub fn new(config: SmtpConfig) -> Result<Self> {
+        let err = |e: lettre::transport::smtp::Error| Error::Config(format!("invalid SMTP server `{}`: {}", config.host, e));
+
+        let mut builder = match config.tls {
+            Tls::None     => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            Tls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host).map_err(err)?,
+            Tls::Tls      => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host).map_err(err)?,
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some((user, pwd)) = config.credentials {
+            builder = builder.credentials(Credentials::new(user, pwd));
+        }
+
+        Ok(Self { transport: builder.build(), from: config.from, to: config.to, plant_to: config.plant_to })
+    }
+
+    /// sends the digest to its recipients, returning the number of emails sent
+    ///
+    /// Recipients of the whole digest get every section; plant recipients get
+    /// what is for their plant. Empty digests are not sent.
+    pub async fn send(&self, digest: &Digest) -> Result<usize> {
+        let mut sent = 0;
+        if !self.to.is_empty() && !digest.is_empty() {
+            self.send_to(&self.to, digest).await?;
+            sent += 1;
+        }
+
+        for (plant, to) in &self.plant_to {
+            let digest = digest.for_plant(plant);
+            if !digest.is_empty() {
+                self.send_to(to, &digest).await?;
+                sent += 1;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    async fn send_to(&self, to: &[Mailbox], digest: &Digest) -> Result<()> {
+        let message = self.message(to, digest)?;
+
+        info!("sending `{}` to {} recipient(s)", digest.title, to.len());
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| Error::Notify(format!("failed to send `{}`: {}", digest.title, e)))?;
+
+        Ok(())
+    }
+
+    /// builds the email of a digest
+    pub fn message(&self, to: &[Mailbox], digest: &Digest) -> Result<Message> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(&digest.title);
+        for mailbox in to {
+            builder = builder.to(mailbox.clone());
+        }
+
+        let mut body = MultiPart::mixed().singlepart(SinglePart::html(digest.to_html()));
+        if !digest.attached_findings().is_empty() {
+            let report = xlsx::reconciliation_to_buffer(digest.attached_findings())?;
+            // the content type is a valid constant
+            let content_type = ContentType::parse(XLSX_CONTENT_TYPE).expect("invalid xlsx content type");
+
+            body = body.singlepart(Attachment::new("findings.xlsx".into()).body(report, content_type));
+        }
+
+        builder
+            .multipart(body)
+            .map_err(|e| Error::Notify(format!("failed to build `{}`: {}", digest.title, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::api::{Qty, Wbs};
+    use crate::recon::Finding;
+
+    /// a minimal SMTP server that accepts one session and returns the data sent
+    fn smtp_stand_in() -> (u16, thread::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let (mut commands, mut data) = (Vec::new(), String::new());
+
+            stream.write_all(b"220 stand-in\r\n").unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let command = line.trim_end().to_string();
+                line.clear();
+
+                let reply: &[u8] = match command.to_uppercase().as_str() {
+                    cmd if cmd.starts_with("EHLO") => b"250 stand-in\r\n",
+                    "DATA" => {
+                        stream.write_all(b"354 go ahead\r\n").unwrap();
+                        while reader.read_line(&mut line).unwrap() > 0 && line != ".\r\n" {
+                            data.push_str(&line);
+                            line.clear();
+                        }
+                        line.clear();
+                        b"250 queued\r\n"
+                    },
+                    "QUIT" => b"221 bye\r\n",
+                    _ => b"250 ok\r\n",
+                };
+                stream.write_all(reply).unwrap();
+                commands.push(command);
+
+                if commands.last().is_some_and(|cmd| cmd == "QUIT") {
+                    break;
+                }
+            }
+
+            (commands, data)
+        });
+
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn sends_plant_digest_to_plant_recipients() {
+        let (port, server) = smtp_stand_in();
+        let notifier = SmtpNotifier::new(SmtpConfig {
+            host: "127.0.0.1".into(),
+            port: Some(port),
+            tls: Tls::None,
+            credentials: None,
+            from: "sap-watch <watch@example.com>".parse().unwrap(),
+            to: Vec::new(),
+            plant_to: vec![(Plant::Williamsport, vec!["hs02@example.com".parse().unwrap()])],
+        }).unwrap();
+
+        let unmatched = |mark: &str, plant: &str| Finding::UnmatchedBurn {
+            mark: mark.into(), program: "12345".into(), plant: plant.into(), matl_wbs: Wbs::None, qty: Qty(2)
+        };
+        let mut digest = Digest::new("digest");
+        digest.findings(&[unmatched("1200001A-X1", "HS01"), unmatched("1200001A-X2", "HS02")]);
+
+        assert_eq!(notifier.send(&digest).await.unwrap(), 1);
+
+        let (commands, data) = server.join().unwrap();
+        assert!(commands.contains(&"RCPT TO:<hs02@example.com>".to_string()));
+        assert!(data.contains("Subject: digest (HS02)"));
+        // the html part is quoted-printable, so join its soft line breaks
+        let html = data.replace("=\r\n", "");
+        assert!(html.contains("1200001A-X2") && !html.contains("1200001A-X1"));
+        assert!(data.contains("filename=\"findings.xlsx\""));
+    }
+}
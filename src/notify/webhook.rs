@@ -0,0 +1,334 @@
+
+//! critical findings pushed to a webhook (e.g. a Teams or Slack channel)
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ftlog::{info, warn};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use time::OffsetDateTime;
+
+use super::Alert;
+use crate::db::config::{required, var};
+use crate::{state, Error, Result};
+
+/// Webhook notifier configuration
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// url the alerts are posted to
+    pub url: String,
+    /// payload template, if not the default payload (see [`payload`])
+    pub template: Option<String>,
+    /// number of times a failed post is retried
+    pub retries: u32,
+    /// delay before the first retry, doubled for each retry after that
+    pub backoff: Duration,
+    /// how long before an alert that is still found is sent again
+    pub repeat_after: Duration,
+    /// file the sent alerts are kept in, so repeats are not sent after a restart
+    pub state: Option<PathBuf>,
+}
+
+impl WebhookConfig {
+    /// builds the webhook config from the environment
+    ///
+    /// | variable               | use                                                    |
+    /// |------------------------|--------------------------------------------------------|
+    /// | `WEBHOOK_URL`          | url the alerts are posted to                           |
+    /// | `WEBHOOK_TEMPLATE`     | payload template file (default payload if not set)     |
+    /// | `WEBHOOK_RETRIES`      | times a failed post is retried (default 3)             |
+    /// | `WEBHOOK_REPEAT_HOURS` | hours before an alert is sent again (default 24)       |
+    /// | `WEBHOOK_STATE`        | file the sent alerts are kept in (memory if not set)   |
+    pub fn from_env() -> Result<Self> {
+        let number = |name: &str, default: u64| match var(name) {
+            Some(val) => val.parse().map_err(|_| Error::Config(format!("invalid `{}` <{}>", name, val))),
+            None => Ok(default)
+        };
+
+        Ok(Self {
+            url: required("WEBHOOK_URL")?,
+            template: Self::template_from_env()?,
+            retries: number("WEBHOOK_RETRIES", 3)? as u32,
+            backoff: Duration::from_secs(1),
+            repeat_after: Duration::from_secs(number("WEBHOOK_REPEAT_HOURS", 24)? * 60 * 60),
+            state: var("WEBHOOK_STATE").map(PathBuf::from),
+        })
+    }
+
+    /// reads the payload template file in `WEBHOOK_TEMPLATE`, if set
+    ///
+    /// Unlike [`WebhookConfig::from_env`], this does not need `WEBHOOK_URL`, so
+    /// payloads can be built without posting them (see [`payload`]).
+    pub fn template_from_env() -> Result<Option<String>> {
+        match var("WEBHOOK_TEMPLATE") {
+            Some(path) => Ok( Some( fs::read_to_string(&path).map_err(Error::io(format!("reading webhook template `{}`", path)))? ) ),
+            None => Ok( None )
+        }
+    }
+}
+
+/// When each alert was last sent, by [`Alert::key`]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SentAlerts {
+    /// unix time each alert was last sent
+    sent: BTreeMap<String, i64>,
+}
+
+impl SentAlerts {
+    fn load(path: &Path) -> Result<Self> {
+        state::load_or_default(path, "sent alerts")
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        state::save(self, path, "sent alerts")
+    }
+}
+
+/// Posts [`Alert`]s to a webhook, skipping alerts that were already sent
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    config: WebhookConfig,
+    sent: SentAlerts,
+}
+
+impl WebhookNotifier {
+    /// creates a notifier, configured from the environment
+    ///
+    /// see [`WebhookConfig::from_env`] for the variables used
+    pub fn from_env() -> Result<Self> {
+        Self::new(WebhookConfig::from_env()?)
+    }
+
+    /// creates a notifier with a given config
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let sent = match &config.state {
+            Some(path) => SentAlerts::load(path)?,
+            None => SentAlerts::default()
+        };
+
+        Ok(Self { client: reqwest::Client::new(), config, sent })
+    }
+
+    /// the alerts that have not been sent within the repeat window
+    pub fn unsent<'a>(&self, alerts: &'a [Alert]) -> Vec<&'a Alert> {
+        let since = now() - self.config.repeat_after.as_secs() as i64;
+
+        let mut keys = Vec::new();
+        alerts
+            .iter()
+            .filter(|alert| {
+                let key = alert.key();
+                let sent = self.sent.sent.get(&key).is_some_and(|time| *time > since);
+                let repeat = keys.contains(&key);
+                keys.push(key);
+
+                !sent && !repeat
+            })
+            .collect()
+    }
+
+    /// posts the alerts that have not been sent yet, returning the number posted
+    ///
+    /// All new alerts are posted as one payload. Failed posts are retried with
+    /// an exponential backoff; alerts are only marked sent once a post succeeds.
+    pub async fn send(&mut self, title: &str, alerts: &[Alert]) -> Result<usize> {
+        let alerts = self.unsent(alerts);
+        if alerts.is_empty() {
+            return Ok(0);
+        }
+
+        info!("posting {} alert(s) for `{}` to the webhook", alerts.len(), title);
+        self.post(title, &payload(self.config.template.as_deref(), title, &alerts)?).await?;
+
+        let now = now();
+        let since = now - self.config.repeat_after.as_secs() as i64;
+        self.sent.sent.retain(|_, time| *time > since);
+        for alert in &alerts {
+            self.sent.sent.insert(alert.key(), now);
+        }
+        if let Some(path) = &self.config.state {
+            self.sent.save(path)?;
+        }
+
+        Ok(alerts.len())
+    }
+
+    async fn post(&self, title: &str, payload: &str) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            let err = match self.client.post(&self.config.url).header(CONTENT_TYPE, "application/json").body(payload.to_string()).send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) if is_transient(resp.status()) => format!("webhook returned {}", resp.status()),
+                Ok(resp) => return Err( Error::Notify(format!("webhook rejected `{}`: {}", title, resp.status())) ),
+                Err(e) => format!("failed to post to webhook: {}", e),
+            };
+
+            if attempt >= self.config.retries {
+                return Err( Error::Notify(format!("failed to post `{}`: {}", title, err)) );
+            }
+
+            attempt += 1;
+            let delay = self.config.backoff * 2u32.pow(attempt - 1);
+            warn!("{} (retry {} of {} in {:?})", err, attempt, self.config.retries, delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// builds the JSON payload of the alerts, from the template if given
+///
+/// Without a template, the payload is `{"title", "text", "alerts"}`. A template
+/// is any JSON document with these placeholders, replaced by JSON values:
+///
+/// | placeholder  | value                                                  |
+/// |--------------|--------------------------------------------------------|
+/// | `{{title}}`  | title of the alerts (string)                           |
+/// | `{{text}}`   | the alerts as text, one per line (string)              |
+/// | `{{count}}`  | number of alerts (number)                              |
+/// | `{{alerts}}` | the alerts, with their records (array)                 |
+///
+/// e.g. `{"title": {{title}}, "text": {{text}}}` for a Teams incoming webhook
+pub fn payload(template: Option<&str>, title: &str, alerts: &[&Alert]) -> Result<String> {
+    let text = alerts
+        .iter()
+        .map(|alert| alert.key())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let to_json = |value: serde_json::Value| value.to_string();
+
+    let payload = match template {
+        Some(template) => template
+            .replace("{{title}}", &to_json(title.into()))
+            .replace("{{text}}", &to_json(text.into()))
+            .replace("{{count}}", &alerts.len().to_string())
+            .replace("{{alerts}}", &serde_json::to_string(alerts).map_err(|e| Error::parse("alerts", e))?),
+        None => serde_json::json!({ "title": title, "text": text, "alerts": alerts }).to_string()
+    };
+
+    // catch templates that do not expand to JSON before they are posted
+    serde_json::from_str::<serde_json::Value>(&payload)
+        .map_err(|e| Error::Config(format!("webhook template does not expand to JSON: {}", e)))?;
+
+    Ok(payload)
+}
+
+/// if a post may succeed when retried (server errors and rate limiting)
+fn is_transient(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT
+}
+
+fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::api::{Qty, Wbs};
+    use crate::recon::Finding;
+
+    /// a minimal HTTP server that replies with each status in turn and returns the bodies posted
+    fn http_stand_in(statuses: &'static [u16]) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut len = 0;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    if let Some(val) = line.to_lowercase().strip_prefix("content-length:") {
+                        len = val.trim().parse().unwrap();
+                    }
+                    line.clear();
+                }
+
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).unwrap();
+                bodies.push(String::from_utf8(body).unwrap());
+
+                write!(stream, "HTTP/1.1 {} stand-in\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status).unwrap();
+            }
+
+            bodies
+        });
+
+        (url, handle)
+    }
+
+    fn notifier(url: String, template: Option<&str>) -> WebhookNotifier {
+        WebhookNotifier::new(WebhookConfig {
+            url,
+            template: template.map(String::from),
+            retries: 2,
+            backoff: Duration::ZERO,
+            repeat_after: Duration::from_secs(60 * 60),
+            state: None,
+        }).unwrap()
+    }
+
+    fn alerts() -> Vec<Alert> {
+        let unmatched = |mark: &str| Finding::UnmatchedBurn {
+            mark: mark.into(), program: "12345".into(), plant: "HS01".into(), matl_wbs: Wbs::None, qty: Qty(2)
+        };
+        let wbs_mismatch = Finding::WbsMismatch {
+            mark: "1200001A-X3".into(), program: "12345".into(), matl_wbs: Wbs::None, order_wbs: Vec::new()
+        };
+
+        Alert::critical("Findings", &[unmatched("1200001A-X1"), wbs_mismatch, unmatched("1200001A-X1"), unmatched("1200001A-X2")])
+    }
+
+    #[tokio::test]
+    async fn posts_new_alerts_once_with_retry() {
+        let (url, server) = http_stand_in(&[503, 200]);
+        let mut notifier = notifier(url, None);
+
+        assert_eq!(notifier.send("critical findings", &alerts()).await.unwrap(), 2);
+        assert_eq!(notifier.send("critical findings", &alerts()).await.unwrap(), 0);
+
+        let bodies = server.join().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0], bodies[1]);
+
+        let payload: serde_json::Value = serde_json::from_str(&bodies[1]).unwrap();
+        assert_eq!(payload["title"], "critical findings");
+        assert_eq!(payload["alerts"].as_array().unwrap().len(), 2);
+        assert_eq!(payload["alerts"][1]["record"]["mark"], "1200001A-X2");
+    }
+
+    #[tokio::test]
+    async fn rejected_posts_are_not_retried_or_marked_sent() {
+        let (url, server) = http_stand_in(&[400]);
+        let mut notifier = notifier(url, None);
+
+        assert!(notifier.send("critical findings", &alerts()).await.is_err());
+        assert_eq!(notifier.unsent(&alerts()).len(), 2);
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn templates_expand_to_json() {
+        let alerts = alerts();
+        let alerts: Vec<&Alert> = alerts.iter().collect();
+
+        let teams = r#"{"title": {{title}}, "text": {{text}}, "count": {{count}}}"#;
+        let json: serde_json::Value = serde_json::from_str(&payload(Some(teams), "TECO \"burns\"", &alerts).unwrap()).unwrap();
+        assert_eq!(json["title"], "TECO \"burns\"");
+        assert_eq!(json["count"], 3);
+        assert!(json["text"].as_str().unwrap().starts_with("Findings: Finding: Unmatched burn, Mark: 1200001A-X1"));
+
+        let broken = r#"{"text": "{{text}}"}"#;
+        assert!(payload(Some(broken), "title", &alerts).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+
+//! report output
+
+mod records;
+mod table;
+pub mod xlsx;
+
+pub use table::{Color, TableOptions};
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+/// Output format for reports
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// aligned text table
+    #[default]
+    Table,
+    /// comma separated values
+    Csv,
+    /// JSON array of records
+    Json,
+    /// newline delimited JSON, one record per line
+    Ndjson,
+}
+
+/// Severity of a record, used to color table output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// nothing wrong
+    Ok,
+    /// may need attention
+    Warning,
+    /// needs to be fixed
+    Error,
+}
+
+/// A record that can be written as a flat row of a report
+/// 
+/// Used for table and CSV output, JSON output serializes the record itself.
+pub trait Tabular {
+    /// column headers
+    fn header() -> Vec<&'static str>;
+    /// values of the row, in the same order as the header
+    fn row(&self) -> Vec<String>;
+
+    /// indices of numeric columns that are totaled in table output
+    fn total_columns() -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// severity of the record, if it has one
+    fn severity(&self) -> Option<Severity> {
+        None
+    }
+}
+
+/// writes records to `out` in the given format, as plain text
+pub fn write<'a, T, W>(out: W, format: Format, records: impl IntoIterator<Item = &'a T>) -> io::Result<()>
+    where
+        T: Tabular + Serialize + 'a,
+        W: Write
+{
+    write_with(out, format, &TableOptions::default(), records)
+}
+
+/// writes records to `out` in the given format, rendering tables with `opts`
+pub fn write_with<'a, T, W>(out: W, format: Format, opts: &TableOptions, records: impl IntoIterator<Item = &'a T>) -> io::Result<()>
+    where
+        T: Tabular + Serialize + 'a,
+        W: Write
+{
+    match format {
+        Format::Table  => table::write_table(out, records.into_iter().collect(), opts),
+        Format::Csv    => write_csv(out, T::header(), records.into_iter().map(Tabular::row)),
+        Format::Json   => write_json(out, records),
+        Format::Ndjson => write_ndjson(out, records),
+    }
+}
+
+fn write_csv(out: impl Write, header: Vec<&str>, rows: impl Iterator<Item = Vec<String>>) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+
+    writer.write_record(header)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+
+    writer.flush()
+}
+
+fn write_json<'a, T: Serialize + 'a>(mut out: impl Write, records: impl IntoIterator<Item = &'a T>) -> io::Result<()> {
+    let records: Vec<&T> = records.into_iter().collect();
+
+    serde_json::to_writer_pretty(&mut out, &records)?;
+    writeln!(out)
+}
+
+fn write_ndjson<'a, T: Serialize + 'a>(mut out: impl Write, records: impl IntoIterator<Item = &'a T>) -> io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut out, record)?;
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
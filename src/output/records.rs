@@ -0,0 +1,458 @@
+
+//! [`Tabular`] implementations for library types
+
+use itertools::Itertools;
+
+use super::{Severity, Tabular};
+use crate::api::{CogiCause, Order, Qty};
+use crate::db::{BurnedPart, Confirmation, LoadedPart, Program, Remnant, SheetStock};
+use crate::recon::{Change, Conversion, DemandGap, DemandStatus, Finding, FindingChange, MachineScrap, OrderChange, OrderChangeKind, RootCause, SheetUsage, StockDiff, StockShortage, StockStatus};
+
+impl Tabular for BurnedPart {
+    fn header() -> Vec<&'static str> {
+        vec!["Part", "Qty", "Program", "Repeat", "Material", "Wbs", "Location", "Plant", "Area", "Machine", "Sheet", "Archived"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.part.clone(),
+            self.qty.0.to_string(),
+            self.program.clone(),
+            self.repeat_id.to_string(),
+            self.matl.matl.clone(),
+            self.matl.wbs.clone().unwrap_or_default(),
+            self.matl.loc.clone(),
+            self.matl.plant.clone(),
+            format!("{:.3}", self.matl.area.as_in2()),
+            self.machine.clone(),
+            self.sheet.clone(),
+            self.archived.map(|dt| dt.to_string()).unwrap_or_default(),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![1, 8]
+    }
+}
+
+impl Tabular for Program {
+    fn header() -> Vec<&'static str> {
+        vec!["Program", "Repeat", "Machine", "Plant", "Sheet", "Material", "Wbs", "Parts", "Sheet Area", "Nested Area", "Utilization", "Archived"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.repeat_id.to_string(),
+            self.machine.clone(),
+            self.matl.plant.clone(),
+            self.sheet.clone(),
+            self.matl.matl.clone(),
+            self.matl.wbs.clone().unwrap_or_default(),
+            self.parts.0.to_string(),
+            format!("{:.3}", self.matl.area.as_in2()),
+            format!("{:.3}", self.nested_area.as_in2()),
+            format!("{:.1}%", self.utilization() * 100.0),
+            self.archived.map(|dt| dt.to_string()).unwrap_or_default(),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![7, 8, 9]
+    }
+}
+
+impl Tabular for Order {
+    fn header() -> Vec<&'static str> {
+        vec!["Type", "Order", "Mark", "Qty", "Wbs", "Plant"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let order_type = match self {
+            Order::PlannedOrder(_)    => "PR",
+            Order::ProductionOrder(_) => "PP01",
+        };
+
+        let data = self.data();
+        vec![
+            order_type.into(),
+            data.id.to_string(),
+            data.mark.clone(),
+            data.qty.0.to_string(),
+            data.wbs.to_string(),
+            data.plant.to_string(),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![3]
+    }
+}
+
+impl Tabular for Finding {
+    fn header() -> Vec<&'static str> {
+        vec!["Finding", "Mark", "Program", "Plant", "Detail"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let (program, plant, detail) = match self {
+            Self::UnmatchedBurn { program, plant, qty, .. } =>
+                (program.clone(), plant.to_string(), format!("{} burned with no order", qty)),
+            Self::ShortOrder { plant, programs, burned, ordered, .. } =>
+                (programs.join(" "), plant.to_string(), format!("{} burned, {} ordered", burned, ordered)),
+            Self::WbsMismatch { program, matl_wbs, order_wbs, .. } =>
+                (program.clone(), String::new(), format!("material {} for orders {}", matl_wbs, order_wbs.iter().join(", "))),
+            Self::PlantMismatch { program, burned, ordered, .. } =>
+                (program.clone(), burned.to_string(), format!("ordered in {}", ordered.iter().join(", "))),
+        };
+
+        vec![self.kind().into(), self.mark().into(), program, plant, detail]
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self {
+            // project stock burned for another job can be transferred
+            Self::WbsMismatch { .. } => Some(Severity::Warning),
+            _ => Some(Severity::Error)
+        }
+    }
+}
+
+impl Tabular for FindingChange {
+    fn header() -> Vec<&'static str> {
+        vec!["Change", "Finding", "Mark", "Plant/Wbs", "Before", "After"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let qty = |qty: Option<Qty>| qty.map(|qty| qty.0.to_string()).unwrap_or_default();
+
+        vec![
+            self.change.to_string(),
+            self.kind.into(),
+            self.mark.clone(),
+            self.scope.clone(),
+            qty(self.before),
+            qty(self.after),
+        ]
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.change {
+            Change::Resolved   => Some(Severity::Ok),
+            Change::QtyChanged => Some(Severity::Warning),
+            Change::New        => Some(Severity::Error),
+        }
+    }
+}
+
+impl Tabular for OrderChange {
+    fn header() -> Vec<&'static str> {
+        vec!["Change", "Order", "Previous Order", "Mark", "Plant", "Qty Before", "Qty After", "Wbs Before", "Wbs After", "Burned"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let qty = |qty: Option<Qty>| qty.map(|qty| qty.0.to_string()).unwrap_or_default();
+        let wbs = |wbs: &Option<_>| wbs.as_ref().map(ToString::to_string).unwrap_or_default();
+
+        vec![
+            self.change.to_string(),
+            self.id.to_string(),
+            self.previous_id.map(|id| id.to_string()).unwrap_or_default(),
+            self.mark.clone(),
+            self.plant.to_string(),
+            qty(self.qty_before),
+            qty(self.qty_after),
+            wbs(&self.wbs_before),
+            wbs(&self.wbs_after),
+            qty(self.burned),
+        ]
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.change {
+            // burns for the order can no longer be confirmed
+            OrderChangeKind::Deleted if self.burned.is_some() => Some(Severity::Error),
+            OrderChangeKind::Deleted | OrderChangeKind::WbsChanged | OrderChangeKind::QtyChanged => Some(Severity::Warning),
+            OrderChangeKind::Created | OrderChangeKind::Converted => Some(Severity::Ok),
+        }
+    }
+}
+
+impl Tabular for Conversion {
+    fn header() -> Vec<&'static str> {
+        vec!["Plant", "Wbs", "Planned Order", "Mark", "Order Qty", "Burned"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.plant.to_string(),
+            self.wbs.to_string(),
+            self.order.to_string(),
+            self.mark.clone(),
+            self.qty.0.to_string(),
+            self.burned.0.to_string(),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![4, 5]
+    }
+}
+
+impl Tabular for Confirmation {
+    fn header() -> Vec<&'static str> {
+        vec!["Part", "Job", "Shipment", "Storage Location", "Qty", "Material", "Wbs", "Area", "Location", "Plant", "Program"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.part.clone(),
+            self.job.clone(),
+            self.shipment.clone(),
+            self.storage_location.clone(),
+            self.qty.0.to_string(),
+            self.matl.clone(),
+            self.wbs.clone().unwrap_or_default(),
+            format!("{:.3}", self.area.as_in2()),
+            self.loc.clone(),
+            self.plant.clone(),
+            self.program.clone(),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![4, 7]
+    }
+}
+
+impl Tabular for RootCause<'_> {
+    fn header() -> Vec<&'static str> {
+        vec!["Cause", "Material", "Plant", "Location", "Wbs", "Qty", "Programs", "Message"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.cause.to_string(),
+            self.error.matl.clone(),
+            self.error.plant.to_string(),
+            self.error.loc.clone(),
+            self.error.wbs.to_string(),
+            self.error.qty.to_string(),
+            self.programs().join(" "),
+            self.error.message.clone(),
+        ]
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.cause {
+            CogiCause::Other => Some(Severity::Warning),
+            _ => Some(Severity::Error)
+        }
+    }
+}
+
+impl Tabular for StockShortage {
+    fn header() -> Vec<&'static str> {
+        vec!["Program", "Material", "Plant", "Location", "Wbs", "Required", "Available", "Short"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.program.clone(),
+            self.matl.clone(),
+            self.plant.clone(),
+            self.loc.clone(),
+            self.wbs.to_string(),
+            format!("{:.3}", self.required.as_in2()),
+            format!("{:.3}", self.available.as_in2()),
+            format!("{:.3}", self.shortage().as_in2()),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        // the same stock is available to every program, so it is not totaled
+        vec![5, 7]
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Error)
+    }
+}
+
+impl Tabular for LoadedPart {
+    fn header() -> Vec<&'static str> {
+        vec!["Part", "Work Order", "Job", "Wbs", "Required", "Burned", "Nested"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.part.clone(),
+            self.wo_number.clone(),
+            self.job().unwrap_or_default().into(),
+            self.wbs().map(|wbs| wbs.to_string()).unwrap_or_default(),
+            self.qty_required.0.to_string(),
+            self.qty_burned.0.to_string(),
+            self.qty_nested.0.to_string(),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![4, 5, 6]
+    }
+}
+
+impl Tabular for DemandGap {
+    fn header() -> Vec<&'static str> {
+        vec!["Status", "Mark", "Job", "SAP Ordered", "SN Required", "SN Remaining", "SN Nested", "SAP Jobs"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.status.to_string(),
+            self.mark.clone(),
+            self.job.clone().unwrap_or_default(),
+            self.sap_ordered.0.to_string(),
+            self.sn_required.0.to_string(),
+            self.sn_remaining.0.to_string(),
+            self.sn_nested.0.to_string(),
+            self.sap_jobs.join(", "),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![3, 4, 5, 6]
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.status {
+            DemandStatus::NotImported | DemandStatus::WrongWbs => Some(Severity::Error),
+            DemandStatus::NotOrdered  | DemandStatus::QtyMismatch => Some(Severity::Warning),
+        }
+    }
+}
+
+impl Tabular for SheetStock {
+    fn header() -> Vec<&'static str> {
+        vec!["Sheet", "Material", "Wbs", "Location", "Plant", "Length", "Width", "Qty", "Area", "Remnant"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.sheet.clone(),
+            self.matl.matl.clone(),
+            self.matl.wbs.clone().unwrap_or_default(),
+            self.matl.loc.clone(),
+            self.matl.plant.clone(),
+            format!("{:.3}", self.length),
+            format!("{:.3}", self.width),
+            self.qty.0.to_string(),
+            format!("{:.3}", self.matl.area.as_in2()),
+            if self.remnant { "yes".into() } else { String::new() },
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![7, 8]
+    }
+}
+
+impl Tabular for StockDiff {
+    fn header() -> Vec<&'static str> {
+        vec!["Status", "Material", "Plant", "Wbs", "SAP Area", "Sigmanest Area", "SAP Locations", "Sheets"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.status.to_string(),
+            self.matl.clone(),
+            self.plant.clone(),
+            self.wbs.to_string(),
+            format!("{:.3}", self.sap.as_in2()),
+            format!("{:.3}", self.sigmanest.as_in2()),
+            self.sap_locs.join(" "),
+            self.sheets.join(" "),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![4, 5]
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.status {
+            StockStatus::OnlyInSap | StockStatus::OnlyInSigmanest => Some(Severity::Error),
+            StockStatus::AreaMismatch => Some(Severity::Warning),
+        }
+    }
+}
+
+impl Tabular for Remnant {
+    fn header() -> Vec<&'static str> {
+        vec!["Remnant", "Program", "Repeat", "Material", "Wbs", "Location", "Plant", "Area"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.program.clone(),
+            self.repeat_id.to_string(),
+            self.matl.matl.clone(),
+            self.matl.wbs.clone().unwrap_or_default(),
+            self.matl.loc.clone(),
+            self.matl.plant.clone(),
+            format!("{:.3}", self.matl.area.as_in2()),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![7]
+    }
+}
+
+impl Tabular for SheetUsage {
+    fn header() -> Vec<&'static str> {
+        vec!["Program", "Repeat", "Machine", "Sheet", "Material", "Sheet Area", "Consumed", "Remnant Area", "Scrap", "Scrap %", "Remnants"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.program.clone(),
+            self.repeat_id.to_string(),
+            self.machine.clone(),
+            self.sheet.clone(),
+            self.matl.clone(),
+            format!("{:.3}", self.sheet_area.as_in2()),
+            format!("{:.3}", self.consumed.as_in2()),
+            format!("{:.3}", self.remnant_area.as_in2()),
+            format!("{:.3}", self.scrap().as_in2()),
+            format!("{:.1}%", self.scrap_pct() * 100.0),
+            self.remnants.join(" "),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![5, 6, 7, 8]
+    }
+}
+
+impl Tabular for MachineScrap {
+    fn header() -> Vec<&'static str> {
+        vec!["Machine", "Sheets", "Sheet Area", "Consumed", "Remnant Area", "Scrap", "Scrap %"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.machine.clone(),
+            self.sheets.to_string(),
+            format!("{:.3}", self.sheet_area.as_in2()),
+            format!("{:.3}", self.consumed.as_in2()),
+            format!("{:.3}", self.remnant_area.as_in2()),
+            format!("{:.3}", self.scrap.as_in2()),
+            format!("{:.1}%", self.scrap_pct() * 100.0),
+        ]
+    }
+
+    fn total_columns() -> Vec<usize> {
+        vec![1, 2, 3, 4, 5]
+    }
+}
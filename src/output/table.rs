@@ -0,0 +1,175 @@
+
+//! aligned text tables for the terminal
+
+use std::env;
+use std::io::{self, IsTerminal, Write};
+
+use itertools::Itertools;
+use terminal_size::{terminal_size, Width};
+
+use super::{Severity, Tabular};
+
+/// columns are never truncated narrower than this
+const MIN_WIDTH: usize = 6;
+const SEPARATOR: &str = "  ";
+
+const BOLD:   &str = "\x1b[1m";
+const RED:    &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN:  &str = "\x1b[32m";
+const RESET:  &str = "\x1b[0m";
+
+/// When to color table output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Color {
+    /// color if stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    /// always color
+    Always,
+    /// never color
+    Never,
+}
+
+/// Options for rendering a table
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TableOptions {
+    /// maximum width of a line, columns are truncated to fit
+    pub width: Option<usize>,
+    /// color rows by severity
+    pub color: bool,
+}
+
+impl TableOptions {
+    /// options for writing to stdout
+    ///
+    /// Truncation and color are only used if stdout is a terminal.
+    pub fn stdout(color: Color) -> Self {
+        let tty = io::stdout().is_terminal();
+
+        let color = match color {
+            Color::Auto   => tty && env::var_os("NO_COLOR").is_none(),
+            Color::Always => true,
+            Color::Never  => false,
+        };
+        let width = match terminal_size() {
+            Some((Width(width), _)) if tty => Some(width as usize),
+            _ => None
+        };
+
+        Self { width, color }
+    }
+}
+
+pub(super) fn write_table<T: Tabular>(mut out: impl Write, records: Vec<&T>, opts: &TableOptions) -> io::Result<()> {
+    let header = T::header();
+    let rows: Vec<(Vec<String>, Option<Severity>)> = records
+        .iter()
+        .map(|record| (record.row(), record.severity()))
+        .collect();
+    let totals = totals(&header, &T::total_columns(), rows.iter().map(|(row, _)| row));
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows.iter().map(|(row, _)| row).chain(&totals) {
+        for (width, val) in widths.iter_mut().zip(row) {
+            *width = (*width).max(val.chars().count());
+        }
+    }
+    if let Some(max) = opts.width {
+        fit(&mut widths, max);
+    }
+
+    // numbers are right-aligned, so their digits line up
+    let numeric: Vec<bool> = (0..widths.len())
+        .map(|col| rows
+            .iter()
+            .map(|(row, _)| row)
+            .chain(&totals)
+            .filter_map(|row| row.get(col).filter(|val| !val.is_empty() && *val != "Total"))
+            .all(|val| val.parse::<f64>().is_ok())
+        )
+        .collect();
+
+    let line = |vals: &[String]| vals
+        .iter()
+        .zip(widths.iter().zip(&numeric))
+        .map(|(val, (width, numeric))| match numeric {
+            true  => format!("{:>width$}", truncate(val, *width), width = width),
+            false => format!("{:width$}", truncate(val, *width), width = width),
+        })
+        .join(SEPARATOR)
+        .trim_end()
+        .to_string();
+    let rule = widths.iter().map(|w| "-".repeat(*w)).join(SEPARATOR);
+    let paint = |text: String, color: Option<&str>| match color {
+        Some(color) if opts.color => format!("{}{}{}", color, text, RESET),
+        _ => text
+    };
+
+    let header: Vec<String> = header.into_iter().map(String::from).collect();
+    writeln!(out, "{}", paint(line(&header), Some(BOLD)))?;
+    writeln!(out, "{}", rule)?;
+    for (row, severity) in &rows {
+        let color = severity.map(|severity| match severity {
+            Severity::Ok      => GREEN,
+            Severity::Warning => YELLOW,
+            Severity::Error   => RED,
+        });
+
+        writeln!(out, "{}", paint(line(row), color))?;
+    }
+    if let Some(totals) = totals {
+        writeln!(out, "{}", rule)?;
+        writeln!(out, "{}", paint(line(&totals), Some(BOLD)))?;
+    }
+
+    Ok(())
+}
+
+/// sums the total columns of the rows, if there are any
+fn totals<'a>(header: &[&str], columns: &[usize], rows: impl Iterator<Item = &'a Vec<String>>) -> Option<Vec<String>> {
+    if columns.is_empty() {
+        return None;
+    }
+
+    let mut sums = vec![0f64; header.len()];
+    let mut decimal = vec![false; header.len()];
+    for row in rows {
+        for &col in columns {
+            let val = row.get(col).map(String::as_str).unwrap_or_default();
+            sums[col] += val.parse::<f64>().unwrap_or_default();
+            decimal[col] |= val.contains('.');
+        }
+    }
+
+    let mut totals = vec![String::new(); header.len()];
+    totals[0] = String::from("Total");
+    for &col in columns {
+        totals[col] = match decimal[col] {
+            true  => format!("{:.3}", sums[col]),
+            false => format!("{}", sums[col]),
+        };
+    }
+
+    Some(totals)
+}
+
+/// shrinks the widest columns until the line fits in `max`
+fn fit(widths: &mut [usize], max: usize) {
+    let separators = SEPARATOR.len() * widths.len().saturating_sub(1);
+
+    while widths.iter().sum::<usize>() + separators > max {
+        match widths.iter_mut().filter(|w| **w > MIN_WIDTH).max_by_key(|w| **w) {
+            Some(widest) => *widest -= 1,
+            None => break
+        }
+    }
+}
+
+fn truncate(val: &str, width: usize) -> String {
+    if val.chars().count() <= width {
+        return val.into();
+    }
+
+    val.chars().take(width.saturating_sub(1)).chain(std::iter::once('…')).collect()
+}
@@ -0,0 +1,145 @@
+
+//! Excel workbook reports
+
+use std::path::Path;
+
+use itertools::Itertools;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use crate::recon::Finding;
+use crate::{Error, Result};
+
+/// a single cell value of a report sheet
+enum Cell {
+    Text(String),
+    Number(f64),
+}
+
+/// a row of cell values
+type Row = Vec<Cell>;
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Self::Text(value.into())
+    }
+}
+
+impl From<u32> for Cell {
+    fn from(value: u32) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+/// writes the findings of a reconciliation run to an excel workbook
+///
+/// The workbook has a summary sheet followed by one sheet per kind of finding.
+pub fn write_reconciliation(path: impl AsRef<Path>, findings: &[Finding]) -> Result<()> {
+    let path = path.as_ref();
+    let err = |e: XlsxError| Error::Excel(format!("failed to write `{}`: {}", path.display(), e));
+
+    reconciliation_workbook(findings)
+        .and_then(|mut wb| wb.save(path))
+        .map_err(err)
+}
+
+/// the excel workbook of [`write_reconciliation`], as the bytes of an xlsx file
+pub fn reconciliation_to_buffer(findings: &[Finding]) -> Result<Vec<u8>> {
+    reconciliation_workbook(findings)
+        .and_then(|mut wb| wb.save_to_buffer())
+        .map_err(|e| Error::Excel(format!("failed to write reconciliation workbook: {}", e)))
+}
+
+fn reconciliation_workbook(findings: &[Finding]) -> std::result::Result<Workbook, XlsxError> {
+    let mut unmatched = Vec::new();
+    let mut short = Vec::new();
+    let mut wbs = Vec::new();
+    let mut plant = Vec::new();
+    for finding in findings {
+        match finding {
+            Finding::UnmatchedBurn { mark, program, plant, matl_wbs, qty } =>
+                unmatched.push(vec![
+                    mark.as_str().into(),
+                    program.as_str().into(),
+                    plant.to_string().into(),
+                    matl_wbs.to_string().into(),
+                    qty.0.into(),
+                ]),
+            Finding::ShortOrder { mark, plant, programs, order_wbs, burned, ordered } =>
+                short.push(vec![
+                    mark.as_str().into(),
+                    programs.join(", ").into(),
+                    plant.to_string().into(),
+                    order_wbs.iter().join(", ").into(),
+                    burned.0.into(),
+                    ordered.0.into(),
+                    burned.0.saturating_sub(ordered.0).into(),
+                ]),
+            Finding::WbsMismatch { mark, program, matl_wbs, order_wbs } =>
+                wbs.push(vec![
+                    mark.as_str().into(),
+                    program.as_str().into(),
+                    matl_wbs.to_string().into(),
+                    order_wbs.iter().join(", ").into(),
+                ]),
+            Finding::PlantMismatch { mark, program, matl_wbs, burned, ordered } =>
+                plant.push(vec![
+                    mark.as_str().into(),
+                    program.as_str().into(),
+                    matl_wbs.to_string().into(),
+                    burned.to_string().into(),
+                    ordered.iter().join(", ").into(),
+                ]),
+        }
+    }
+
+    let sheets: [(&str, &[&str], Vec<Row>); 4] = [
+        ("Unmatched Burns",  &["Part", "Program", "Plant", "Material Wbs", "Qty"], unmatched),
+        ("Short Orders",     &["Part", "Programs", "Plant", "Order Wbs", "Burned", "Ordered", "Short"], short),
+        ("WBS Mismatches",   &["Part", "Program", "Material Wbs", "Order Wbs"], wbs),
+        ("Plant Mismatches", &["Part", "Program", "Material Wbs", "Burned Plant", "Ordered Plants"], plant),
+    ];
+
+    let bold = Format::new().set_bold();
+    let mut wb = Workbook::new();
+
+    let summary = wb.add_worksheet().set_name("Summary")?;
+    let summary_rows = sheets
+        .iter()
+        .map(|(name, _, rows)| vec![Cell::from(*name), Cell::from(rows.len() as u32)])
+        .chain(std::iter::once(vec![Cell::from("Total"), Cell::from(findings.len() as u32)]))
+        .collect();
+    write_sheet(summary, &bold, &["Finding", "Count"], summary_rows)?;
+
+    for (name, header, rows) in sheets {
+        let sheet = wb.add_worksheet().set_name(name)?;
+        write_sheet(sheet, &bold, header, rows)?;
+    }
+
+    Ok(wb)
+}
+
+fn write_sheet(sheet: &mut Worksheet, bold: &Format, header: &[&str], rows: Vec<Row>) -> std::result::Result<(), XlsxError> {
+    sheet.write_row_with_format(0, 0, header.iter().copied(), bold)?;
+
+    for (i, row) in rows.iter().enumerate() {
+        let row_num = i as u32 + 1;
+        for (col, cell) in row.iter().enumerate() {
+            match cell {
+                Cell::Text(val)   => sheet.write_string(row_num, col as u16, val)?,
+                Cell::Number(val) => sheet.write_number(row_num, col as u16, *val)?,
+            };
+        }
+    }
+
+    sheet.autofilter(0, 0, rows.len() as u32, header.len() as u16 - 1)?;
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofit();
+
+    Ok(())
+}
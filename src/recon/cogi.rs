@@ -0,0 +1,70 @@
+
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::api::{CogiCause, CogiError, Wbs};
+use crate::db::BurnedPart;
+
+/// A COGI error linked back to the Sigmanest burns it originated from
+#[derive(Debug, Serialize)]
+pub struct RootCause<'a> {
+    /// the COGI error
+    pub error: CogiError,
+    /// the classified cause of the error
+    pub cause: CogiCause,
+    /// burned parts that consumed the errored material
+    pub parts: Vec<&'a BurnedPart>,
+}
+
+impl<'a> RootCause<'a> {
+    /// links a COGI error to the burned parts that consumed its material
+    pub fn new(error: CogiError, burns: &'a [BurnedPart]) -> Self {
+        let cause = error.cause();
+        let parts = burns
+            .iter()
+            .filter(|part| is_from_error(part, &error))
+            .collect();
+
+        Self { error, cause, parts }
+    }
+
+    /// the distinct programs that consumed the errored material
+    pub fn programs(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .map(|part| part.program.as_str())
+            .unique()
+            .sorted()
+            .collect()
+    }
+}
+
+/// links COGI errors to the burned parts they originated from,
+/// grouped by the classified cause
+pub fn root_causes(errors: Vec<CogiError>, burns: &[BurnedPart]) -> BTreeMap<CogiCause, Vec<RootCause<'_>>> {
+    let mut report = BTreeMap::<CogiCause, Vec<RootCause>>::new();
+
+    for error in errors {
+        let root = RootCause::new(error, burns);
+        report.entry(root.cause.clone()).or_default().push(root);
+    }
+
+    report
+}
+
+fn is_from_error(part: &BurnedPart, error: &CogiError) -> bool {
+    if part.matl.matl != error.matl || part.matl.plant != error.plant.to_string() {
+        return false;
+    }
+
+    // plant stock errors can come from any burn of the material
+    if let Wbs::None = error.wbs {
+        return true;
+    }
+
+    match part.matl.wbs.as_deref().map(Wbs::try_from) {
+        Some(Ok(wbs)) => wbs == error.wbs,
+        _ => false
+    }
+}
@@ -0,0 +1,148 @@
+
+use itertools::Itertools;
+
+use crate::api::{Order, Plant, Qty, Wbs};
+use crate::db::PartBurnedQty;
+
+/// A planned order with burns that must be converted to a production order before they can be confirmed
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversion {
+    /// plant of the order
+    pub plant: Plant,
+    /// WBS element of the order
+    pub wbs: Wbs,
+    /// planned order number
+    pub order: u32,
+    /// piece mark
+    pub mark: String,
+    /// order quantity
+    pub qty: Qty,
+    /// quantity burned in Sigmanest against the order
+    pub burned: Qty,
+}
+
+impl Conversion {
+    /// the order as a line of a CO41 (collective conversion) selection list
+    pub fn to_co41_line(&self) -> String {
+        self.order.to_string()
+    }
+}
+
+/// finds the planned orders that need converting, grouped by plant and WBS element
+///
+/// `burned` is the quantity burned to date for each mark, by plant and WBS
+/// element of the material burned. Burns are applied to the mark's orders at the
+/// plant they were burned at: burns from project stock to the orders of that
+/// job, then burns from plant stock to the orders of any job. Within those,
+/// production orders are applied first, since they can already be confirmed,
+/// and what is left goes to planned orders in order number order. Burns over
+/// the order quantity are left on the last planned order.
+pub fn conversions(orders: &[Order], burned: &[PartBurnedQty]) -> Vec<Conversion> {
+    let by_mark = orders
+        .iter()
+        .into_group_map_by(|order| (order.data().mark.as_str(), order.data().plant.clone()));
+
+    // burns from project stock first, so plant stock fills what is left
+    let burned = burned
+        .iter()
+        .filter_map(|part| {
+            let plant = Plant::try_from(part.plant.as_str()).ok()?;
+            let wbs = part.wbs.as_deref().and_then(|wbs| Wbs::try_from(wbs).ok()).unwrap_or_default();
+
+            Some(((part.part.as_str(), plant), wbs, part.qty))
+        })
+        .sorted_by_key(|(_, wbs, _)| wbs.job().is_none())
+        .into_group_map_by(|(key, ..)| key.clone());
+
+    let mut conversions = Vec::new();
+    for (key, burned) in burned {
+        let Some(orders) = by_mark.get(&key) else {
+            continue;
+        };
+
+        // (order, quantity not yet burned against, quantity burned against)
+        let mut applied: Vec<(&Order, Qty, Qty)> = orders
+            .iter()
+            .map(|order| (*order, order.data().qty, Qty(0)))
+            .collect();
+        applied.sort_by_key(|(order, ..)| (matches!(order, Order::PlannedOrder(_)), order.data().id));
+
+        for (_, wbs, qty) in burned {
+            let for_job = |order: &Order| wbs.job().is_none_or(|job| order.data().wbs.job() == Some(job));
+
+            let mut remaining = qty;
+            for (_, open, burned) in applied.iter_mut().filter(|(order, ..)| for_job(order)) {
+                let take = remaining.min(*open);
+                *open = open.checked_sub(take).unwrap_or_default();
+                *burned += take;
+                remaining = remaining.checked_sub(take).unwrap_or_default();
+            }
+
+            let last_planned = applied
+                .iter_mut()
+                .rfind(|(order, ..)| for_job(order) && matches!(order, Order::PlannedOrder(_)));
+            if let Some((_, _, burned)) = last_planned {
+                *burned += remaining;
+            }
+        }
+
+        for (order, _, burned) in applied {
+            if let Order::PlannedOrder(data) = order {
+                if burned > Qty(0) {
+                    conversions.push(Conversion {
+                        plant: data.plant.clone(),
+                        wbs: data.wbs.clone(),
+                        order: data.id,
+                        mark: data.mark.clone(),
+                        qty: data.qty,
+                        burned,
+                    });
+                }
+            }
+        }
+    }
+
+    conversions.sort_by(|a, b| a.plant.to_string()
+        .cmp(&b.plant.to_string())
+        .then_with(|| a.wbs.cmp(&b.wbs))
+        .then_with(|| a.mark.cmp(&b.mark))
+        .then(a.order.cmp(&b.order))
+    );
+    conversions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::OrderData;
+
+    fn planned(id: u32, qty: u32, wbs: &str, plant: Plant) -> Order {
+        Order::PlannedOrder(OrderData { id, mark: "1200001A-B1".into(), qty: Qty(qty), wbs: Wbs::try_from(wbs).unwrap(), plant })
+    }
+
+    fn burned(plant: &str, wbs: Option<&str>, qty: u32) -> PartBurnedQty {
+        PartBurnedQty { part: "1200001A-B1".into(), plant: plant.into(), wbs: wbs.map(String::from), qty: Qty(qty) }
+    }
+
+    fn applied(conversions: &[Conversion]) -> Vec<(u32, Qty)> {
+        conversions.iter().map(|conversion| (conversion.order, conversion.burned)).collect()
+    }
+
+    #[test]
+    fn burns_are_applied_at_their_plant() {
+        let orders = [planned(1, 2, "D-1200001-10002", Plant::Lancaster), planned(2, 2, "D-1200001-10002", Plant::Williamsport)];
+        let burned = [burned("HS02", None, 1)];
+
+        assert_eq!(applied(&conversions(&orders, &burned)), [(2, Qty(1))]);
+    }
+
+    #[test]
+    fn project_stock_burns_are_applied_to_their_job() {
+        let orders = [planned(1, 2, "D-1200001-10002", Plant::Lancaster), planned(2, 2, "D-1200002-10002", Plant::Lancaster)];
+        let burned = [burned("HS01", None, 1), burned("HS01", Some("D-1200002-10001"), 3)];
+
+        // the plant stock burn fills the order left after the job's burns
+        assert_eq!(applied(&conversions(&orders, &burned)), [(1, Qty(1)), (2, Qty(3))]);
+    }
+}
@@ -0,0 +1,170 @@
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use crate::api::{Order, Qty};
+use crate::db::LoadedPart;
+
+/// piece mark and job number demand is keyed by
+type DemandKey = (String, Option<String>);
+
+/// How SAP demand and the parts loaded in Sigmanest disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DemandStatus {
+    /// SAP has open orders that were never loaded into Sigmanest
+    NotImported,
+    /// loaded into Sigmanest, but SAP has no orders for the part
+    NotOrdered,
+    /// loaded into Sigmanest for a job that SAP has no orders for the part on
+    WrongWbs,
+    /// loaded for the right job, but the quantities do not agree
+    QtyMismatch,
+}
+
+impl Display for DemandStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use DemandStatus::*;
+
+        let name = match self {
+            NotImported => "Not imported",
+            NotOrdered  => "Not ordered",
+            WrongWbs    => "Wrong WBS",
+            QtyMismatch => "Qty mismatch",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A part whose SAP order quantity does not match what is loaded in Sigmanest
+#[derive(Debug, Clone, Serialize)]
+pub struct DemandGap {
+    /// part name (piece mark)
+    pub mark: String,
+    /// job number the demand is for
+    pub job: Option<String>,
+    /// how the demand disagrees
+    pub status: DemandStatus,
+    /// total quantity on SAP orders (COHV order quantity, including what is confirmed)
+    pub sap_ordered: Qty,
+    /// quantity required on the Sigmanest work orders (including what is burned)
+    pub sn_required: Qty,
+    /// quantity loaded in Sigmanest and not yet burned
+    pub sn_remaining: Qty,
+    /// quantity nested on programs that have not been burned
+    pub sn_nested: Qty,
+    /// jobs SAP has orders for the part on (for [`DemandStatus::WrongWbs`])
+    pub sap_jobs: Vec<String>,
+}
+
+#[derive(Default)]
+struct Loaded {
+    required: Qty,
+    remaining: Qty,
+    nested: Qty,
+}
+
+/// compares SAP order quantities against the parts loaded in Sigmanest
+///
+/// COHV only has the total order quantity, so it is compared against the total
+/// quantity required in Sigmanest, burned or not.
+pub fn compare_demand(orders: &[Order], loaded: &[LoadedPart]) -> Vec<DemandGap> {
+    let mut sap = BTreeMap::<DemandKey, Qty>::new();
+    for data in orders.iter().map(Order::data) {
+        let key = (data.mark.clone(), data.wbs.job().map(String::from));
+        *sap.entry(key).or_default() += data.qty;
+    }
+
+    let mut sn = BTreeMap::<DemandKey, Loaded>::new();
+    for part in loaded {
+        let key = (part.part.clone(), part.job().map(String::from));
+        let entry = sn.entry(key).or_default();
+        entry.required += part.qty_required;
+        entry.remaining += part.qty_remaining();
+        entry.nested += part.qty_nested;
+    }
+
+    let sap_jobs = |mark: &str| sap
+        .keys()
+        .filter(|(m, _)| m == mark)
+        .filter_map(|(_, job)| job.clone())
+        .collect::<Vec<_>>();
+
+    let mut gaps = Vec::new();
+    let keys: BTreeSet<&DemandKey> = sap.keys().chain(sn.keys()).collect();
+    for key @ (mark, job) in keys {
+        let sap_ordered = sap.get(key).copied();
+        let sn_loaded = sn.get(key);
+
+        let status = match (sap_ordered, sn_loaded) {
+            (Some(ordered), None) if ordered > Qty(0) => DemandStatus::NotImported,
+            (None, Some(loaded)) if loaded.remaining > Qty(0) => match sap_jobs(mark).is_empty() {
+                true  => DemandStatus::NotOrdered,
+                false => DemandStatus::WrongWbs,
+            },
+            (Some(ordered), Some(loaded)) if ordered != loaded.required => DemandStatus::QtyMismatch,
+            _ => continue
+        };
+
+        gaps.push(DemandGap {
+            mark: mark.clone(),
+            job: job.clone(),
+            status,
+            sap_ordered: sap_ordered.unwrap_or_default(),
+            sn_required: sn_loaded.map(|loaded| loaded.required).unwrap_or_default(),
+            sn_remaining: sn_loaded.map(|loaded| loaded.remaining).unwrap_or_default(),
+            sn_nested: sn_loaded.map(|loaded| loaded.nested).unwrap_or_default(),
+            sap_jobs: match status {
+                DemandStatus::WrongWbs => sap_jobs(mark),
+                _ => Vec::new()
+            },
+        });
+    }
+
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::{OrderData, Plant, Wbs};
+
+    fn order(qty: u32) -> Order {
+        Order::ProductionOrder(OrderData {
+            id: 1,
+            mark: "1200001A-B1".into(),
+            qty: Qty(qty),
+            wbs: Wbs::try_from("D-1200001-10002").unwrap(),
+            plant: Plant::Lancaster,
+        })
+    }
+
+    fn loaded(required: u32, burned: u32) -> LoadedPart {
+        LoadedPart {
+            part: "1200001A-B1".into(),
+            wo_number: "1200001A-1".into(),
+            qty_required: Qty(required),
+            qty_nested: Qty(0),
+            qty_burned: Qty(burned),
+            data1: "1200001A".into(),
+            data2: "1".into(),
+        }
+    }
+
+    #[test]
+    fn partially_burned_part_matches_its_order() {
+        assert!(compare_demand(&[order(4)], &[loaded(4, 3)]).is_empty());
+    }
+
+    #[test]
+    fn order_qty_is_compared_against_qty_required() {
+        let gaps = compare_demand(&[order(4)], &[loaded(5, 3)]);
+
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].status, DemandStatus::QtyMismatch);
+        assert_eq!(gaps[0].sap_ordered, Qty(4));
+        assert_eq!(gaps[0].sn_required, Qty(5));
+        assert_eq!(gaps[0].sn_remaining, Qty(2));
+    }
+}
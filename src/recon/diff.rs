@@ -0,0 +1,377 @@
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use super::Finding;
+use crate::api::{Order, OrderData, Plant, Qty, Wbs};
+use crate::db::BurnedPart;
+use crate::{Error, Result};
+
+/// findings of a run, keyed by mark, kind of finding and plant/WBS, with their total quantity
+type Totals = BTreeMap<(String, &'static str, String), Option<Qty>>;
+
+/// How a finding changed between two reconciliation runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Change {
+    /// found in the first run, but not the second
+    Resolved,
+    /// found in the second run, but not the first
+    New,
+    /// found in both runs, with a different quantity
+    QtyChanged,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Resolved   => "Resolved",
+            Self::New        => "New",
+            Self::QtyChanged => "Qty changed",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A finding that differs between two reconciliation runs
+#[derive(Debug, Clone, Serialize)]
+pub struct FindingChange {
+    /// how the finding changed
+    pub change: Change,
+    /// the kind of finding (see [`Finding::kind`])
+    pub kind: &'static str,
+    /// part name (piece mark)
+    pub mark: String,
+    /// plant or WBS element the finding is for (see [`Finding::scope`])
+    pub scope: String,
+    /// quantity out of balance in the first run
+    pub before: Option<Qty>,
+    /// quantity out of balance in the second run
+    pub after: Option<Qty>,
+}
+
+fn totals(findings: &[Finding]) -> Totals {
+    let mut totals = Totals::new();
+    for finding in findings {
+        let total = totals.entry((finding.mark().into(), finding.kind(), finding.scope())).or_default();
+        if let Some(qty) = finding.qty() {
+            *total = Some(total.unwrap_or_default() + qty);
+        }
+    }
+
+    totals
+}
+
+/// compares the findings of two reconciliation runs, by mark and plant/WBS
+pub fn diff_findings(before: &[Finding], after: &[Finding]) -> Vec<FindingChange> {
+    let before = totals(before);
+    let after = totals(after);
+
+    let mut changes = Vec::new();
+    let keys: BTreeSet<_> = before.keys().chain(after.keys()).collect();
+    for key @ (mark, kind, scope) in keys {
+        let (was, now) = (before.get(key), after.get(key));
+        let change = match (was, now) {
+            (Some(_), None) => Change::Resolved,
+            (None, Some(_)) => Change::New,
+            (Some(was), Some(now)) if was != now => Change::QtyChanged,
+            _ => continue
+        };
+
+        changes.push(FindingChange {
+            change,
+            kind,
+            mark: mark.clone(),
+            scope: scope.clone(),
+            before: was.copied().flatten(),
+            after: now.copied().flatten(),
+        });
+    }
+
+    changes
+}
+
+/// loads the findings of a stored reconciliation run (`recon reconcile` output as json or ndjson)
+pub fn load_findings(path: impl AsRef<Path>) -> Result<Vec<Finding>> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .map_err(Error::io(format!("reading reconciliation run `{}`", path.display())))?;
+
+    let findings = match text.trim_start().starts_with('[') {
+        true  => serde_json::from_str(&text),
+        false => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect(),
+    };
+
+    findings.map_err(|e| Error::parse("reconciliation run", e))
+}
+
+/// How an order changed between two COHV exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OrderChangeKind {
+    /// only in the later export
+    Created,
+    /// only in the earlier export
+    Deleted,
+    /// a planned order (PR) that is now a production order (PP01)
+    Converted,
+    /// the order quantity changed
+    QtyChanged,
+    /// the order was moved to another WBS element
+    WbsChanged,
+}
+
+impl Display for OrderChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Created    => "Created",
+            Self::Deleted    => "Deleted",
+            Self::Converted  => "Converted",
+            Self::QtyChanged => "Qty changed",
+            Self::WbsChanged => "WBS changed",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// An order that differs between two COHV exports
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderChange {
+    /// how the order changed
+    pub change: OrderChangeKind,
+    /// order number (in the later export, unless deleted)
+    pub id: u32,
+    /// order number of the planned order a production order was converted from,
+    /// if conversion gave it a new number
+    pub previous_id: Option<u32>,
+    /// piece mark
+    pub mark: String,
+    /// plant of the order
+    pub plant: Plant,
+    /// order quantity in the earlier export
+    pub qty_before: Option<Qty>,
+    /// order quantity in the later export
+    pub qty_after: Option<Qty>,
+    /// WBS element in the earlier export
+    pub wbs_before: Option<Wbs>,
+    /// WBS element in the later export
+    pub wbs_after: Option<Wbs>,
+    /// quantity of the mark burned at the plant that the orders left cannot confirm
+    /// (for deleted orders, see [`burned_on_deleted`])
+    pub burned: Option<Qty>,
+}
+
+impl OrderChange {
+    fn new(change: OrderChangeKind, data: &OrderData, before: Option<&OrderData>, after: Option<&OrderData>) -> Self {
+        Self {
+            change,
+            id: data.id,
+            previous_id: before.map(|data| data.id).filter(|id| *id != data.id),
+            mark: data.mark.clone(),
+            plant: data.plant.clone(),
+            qty_before: before.map(|data| data.qty),
+            qty_after: after.map(|data| data.qty),
+            wbs_before: before.map(|data| data.wbs.clone()),
+            wbs_after: after.map(|data| data.wbs.clone()),
+            burned: None,
+        }
+    }
+}
+
+/// compares the orders of two COHV exports by order number
+///
+/// Converting a planned order can give the production order a new number,
+/// so a deleted planned order and a created production order for the same
+/// mark, WBS element and plant are reported as a conversion.
+pub fn diff_orders(before: &[Order], after: &[Order]) -> Vec<OrderChange> {
+    let before_ids: HashMap<u32, &Order> = before.iter().map(|order| (order.data().id, order)).collect();
+    let after_ids: HashMap<u32, &Order> = after.iter().map(|order| (order.data().id, order)).collect();
+
+    let mut changes = Vec::new();
+    for order in after {
+        if let Some(old) = before_ids.get(&order.data().id) {
+            compare_orders(old, order, &mut changes);
+        }
+    }
+
+    let mut created: Vec<&Order> = after.iter().filter(|order| !before_ids.contains_key(&order.data().id)).collect();
+    for old in before.iter().filter(|order| !after_ids.contains_key(&order.data().id)) {
+        let converted = match old {
+            Order::PlannedOrder(data) => created.iter().position(|order| matches!(order,
+                Order::ProductionOrder(new) if new.mark == data.mark && new.wbs == data.wbs && new.plant == data.plant
+            )),
+            Order::ProductionOrder(_) => None,
+        };
+
+        match converted {
+            Some(i) => compare_orders(old, created.remove(i), &mut changes),
+            None => changes.push(OrderChange::new(OrderChangeKind::Deleted, old.data(), Some(old.data()), None)),
+        }
+    }
+
+    for order in created {
+        changes.push(OrderChange::new(OrderChangeKind::Created, order.data(), None, Some(order.data())));
+    }
+
+    changes.sort_by(|a, b| a.mark.cmp(&b.mark).then(a.id.cmp(&b.id)));
+    changes
+}
+
+fn compare_orders(old: &Order, new: &Order, changes: &mut Vec<OrderChange>) {
+    let (before, after) = (old.data(), new.data());
+    let mut push = |change| changes.push(OrderChange::new(change, after, Some(before), Some(after)));
+
+    if matches!((old, new), (Order::PlannedOrder(_), Order::ProductionOrder(_))) {
+        push(OrderChangeKind::Converted);
+    }
+    if before.qty != after.qty {
+        push(OrderChangeKind::QtyChanged);
+    }
+    if before.wbs != after.wbs {
+        push(OrderChangeKind::WbsChanged);
+    }
+}
+
+/// sets the quantity burned for the mark and plant of each deleted order,
+/// if the burns can no longer be confirmed
+///
+/// That is when the later export has no order left for the mark at the plant,
+/// or the orders left are for less than was burned.
+pub fn burned_on_deleted(changes: &mut [OrderChange], burns: &[BurnedPart], after: &[Order]) {
+    let mut burned = HashMap::<(&str, String), Qty>::new();
+    for part in burns {
+        *burned.entry((part.part.as_str(), part.matl.plant.clone())).or_default() += part.qty;
+    }
+
+    let mut ordered = HashMap::<(&str, String), Qty>::new();
+    for data in after.iter().map(Order::data) {
+        *ordered.entry((data.mark.as_str(), data.plant.to_string())).or_default() += data.qty;
+    }
+
+    for change in changes.iter_mut().filter(|change| change.change == OrderChangeKind::Deleted) {
+        let key = (change.mark.as_str(), change.plant.to_string());
+        change.burned = burned
+            .get(&key)
+            .copied()
+            .filter(|burned| ordered.get(&key).is_none_or(|ordered| burned > ordered));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::db::MaterialData;
+
+    fn unmatched(mark: &str, qty: u32) -> Finding {
+        Finding::UnmatchedBurn {
+            mark: mark.into(),
+            program: "12345".into(),
+            plant: "HS01".into(),
+            matl_wbs: Wbs::None,
+            qty: Qty(qty),
+        }
+    }
+
+    #[test]
+    fn findings_are_resolved_new_or_changed() {
+        let before = [unmatched("1200001A-B1", 2), unmatched("1200001A-B2", 1), unmatched("1200001A-B3", 4)];
+        let after = [unmatched("1200001A-B2", 1), unmatched("1200001A-B3", 2), unmatched("1200001A-B3", 3), unmatched("1200001A-B4", 1)];
+
+        let changes: Vec<_> = diff_findings(&before, &after)
+            .into_iter()
+            .map(|change| (change.mark, change.change, change.before, change.after))
+            .collect();
+
+        assert_eq!(changes, [
+            ("1200001A-B1".into(), Change::Resolved, Some(Qty(2)), None),
+            ("1200001A-B3".into(), Change::QtyChanged, Some(Qty(4)), Some(Qty(5))),
+            ("1200001A-B4".into(), Change::New, None, Some(Qty(1))),
+        ]);
+    }
+
+    fn data(id: u32, mark: &str, qty: u32, plant: Plant) -> OrderData {
+        OrderData { id, mark: mark.into(), qty: Qty(qty), wbs: Wbs::try_from("D-1200001-10002").unwrap(), plant }
+    }
+
+    fn planned(id: u32, mark: &str, qty: u32) -> Order {
+        Order::PlannedOrder(data(id, mark, qty, Plant::Lancaster))
+    }
+
+    fn production(id: u32, mark: &str, qty: u32) -> Order {
+        Order::ProductionOrder(data(id, mark, qty, Plant::Lancaster))
+    }
+
+    fn burn(mark: &str, qty: u32) -> BurnedPart {
+        BurnedPart {
+            part: mark.into(),
+            qty: Qty(qty),
+            matl: MaterialData {
+                matl: "50/50W-0500".into(),
+                wbs: None,
+                loc: "K2".into(),
+                plant: "HS01".into(),
+                area: Default::default(),
+            },
+            program: "12345".into(),
+            repeat_id: 1,
+            packet_id: 1,
+            machine: "Gemini".into(),
+            sheet: "S1".into(),
+            archived: None,
+        }
+    }
+
+    fn kinds(changes: &[OrderChange]) -> Vec<(OrderChangeKind, u32, Option<u32>)> {
+        changes.iter().map(|change| (change.change, change.id, change.previous_id)).collect()
+    }
+
+    #[test]
+    fn orders_are_created_deleted_and_changed() {
+        let before = [planned(1, "1200001A-B1", 2), production(2, "1200001A-B2", 3)];
+        let after = [planned(1, "1200001A-B1", 4), production(3, "1200001A-B3", 1)];
+
+        assert_eq!(kinds(&diff_orders(&before, &after)), [
+            (OrderChangeKind::QtyChanged, 1, None),
+            (OrderChangeKind::Deleted, 2, None),
+            (OrderChangeKind::Created, 3, None),
+        ]);
+    }
+
+    #[test]
+    fn planned_order_converted_under_a_new_number() {
+        let before = [planned(1, "1200001A-B1", 2)];
+        let after = [production(5, "1200001A-B1", 2)];
+
+        assert_eq!(kinds(&diff_orders(&before, &after)), [(OrderChangeKind::Converted, 5, Some(1))]);
+    }
+
+    #[test]
+    fn burns_on_deleted_order_covered_by_remaining_orders() {
+        let before = [planned(1, "1200001A-B1", 2), planned(2, "1200001A-B1", 2)];
+        let after = [planned(2, "1200001A-B1", 2)];
+
+        let mut changes = diff_orders(&before, &after);
+        burned_on_deleted(&mut changes, &[burn("1200001A-B1", 2)], &after);
+        assert_eq!(changes[0].change, OrderChangeKind::Deleted);
+        assert_eq!(changes[0].burned, None);
+
+        burned_on_deleted(&mut changes, &[burn("1200001A-B1", 3)], &after);
+        assert_eq!(changes[0].burned, Some(Qty(3)));
+    }
+
+    #[test]
+    fn burns_on_deleted_order_with_no_orders_left() {
+        let before = [planned(1, "1200001A-B1", 2)];
+
+        let mut changes = diff_orders(&before, &[]);
+        burned_on_deleted(&mut changes, &[burn("1200001A-B1", 1), burn("1200001A-B2", 4)], &[]);
+        assert_eq!(changes[0].burned, Some(Qty(1)));
+    }
+}
@@ -0,0 +1,20 @@
+
+//! reconciliation of Sigmanest burns against SAP exports
+
+mod cogi;
+mod convert;
+mod demand;
+mod diff;
+mod posted;
+mod reconcile;
+mod remnant;
+mod stock;
+
+pub use cogi::{RootCause, root_causes};
+pub use convert::{Conversion, conversions};
+pub use demand::{DemandGap, DemandStatus, compare_demand};
+pub use diff::{Change, FindingChange, OrderChange, OrderChangeKind, burned_on_deleted, diff_findings, diff_orders, load_findings};
+pub use posted::{BurnStatus, Postings, outstanding_burns};
+pub use reconcile::{Finding, reconcile, reconcile_to_date};
+pub use remnant::{MachineScrap, SheetUsage, scrap_by_machine, sheet_usage};
+pub use stock::{StockDiff, StockShortage, StockStatus, check_stock, compare_stock};
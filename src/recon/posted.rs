@@ -0,0 +1,202 @@
+
+use std::collections::HashMap;
+
+use ftlog::{info, warn};
+
+use crate::api::{Area, MaterialDocument, MovementType, Qty};
+use crate::db::BurnedPart;
+
+/// SAP posts quantities to 3 decimals, so a posted area (in FT2) can be short
+/// of the area burned by up to half of the last decimal
+const AREA_ROUNDING_FT2: f64 = 0.0005;
+
+/// Quantities SAP has already received, keyed by the Sigmanest program
+/// in the material document reference
+#[derive(Debug, Default)]
+pub struct Postings {
+    /// pieces received (101), by program and part
+    parts: HashMap<(String, String), Qty>,
+    /// area consumed (261 less 262), by program and material
+    consumed: HashMap<(String, String), Area>,
+    /// postings not applied to burns (by-products and postings that failed to parse)
+    skipped: usize,
+}
+
+impl Postings {
+    /// totals the material documents by program
+    pub fn new(docs: &[MaterialDocument]) -> Self {
+        let mut postings = Self::default();
+
+        for doc in docs.iter().filter(|doc| !doc.reference.is_empty()) {
+            let key = (doc.reference.clone(), doc.matl.clone());
+            let res = match doc.movement {
+                MovementType::GoodsReceipt       => doc.qty.as_qty().map(|qty| *postings.parts.entry(key).or_default() += qty),
+                MovementType::GoodsIssue         => doc.qty.as_area().map(|area| *postings.consumed.entry(key).or_default() += area),
+                MovementType::GoodsIssueReversal => doc.qty.as_area().map(|area| *postings.consumed.entry(key).or_default() -= area),
+                // remnants returned to stock are not matched against burns
+                MovementType::ByProduct          => {
+                    info!("skipping {} posting of `{}` for `{}`: by-products are not matched to burns", doc.movement.code(), doc.matl, doc.reference);
+                    postings.skipped += 1;
+                    continue;
+                }
+            };
+
+            if let Err(e) = res {
+                warn!("skipping {} posting of `{}` for `{}`: {}", doc.movement.code(), doc.matl, doc.reference, e);
+                postings.skipped += 1;
+            }
+        }
+
+        postings
+    }
+
+    /// number of postings not applied to burns
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// applies the postings to burned parts, in order, marking
+    /// what SAP has already received for each burn
+    pub fn apply<'a>(&self, burns: &'a [BurnedPart]) -> Vec<BurnStatus<'a>> {
+        let mut parts = self.parts.clone();
+        let mut consumed = self.consumed.clone();
+
+        burns
+            .iter()
+            .map(|part| {
+                let part_posted = take(&mut parts, (part.program.clone(), part.part.clone()), part.qty);
+                let matl_posted = take(&mut consumed, (part.program.clone(), part.matl.matl.clone()), part.matl.area);
+
+                BurnStatus { part, part_posted, matl_posted }
+            })
+            .collect()
+    }
+}
+
+/// the posting status of a burned part
+#[derive(Debug)]
+pub struct BurnStatus<'a> {
+    /// the burned part
+    pub part: &'a BurnedPart,
+    /// if the part's goods receipt (101) is posted
+    pub part_posted: bool,
+    /// if the program's material consumption (261) for the part is posted
+    pub matl_posted: bool,
+}
+
+impl BurnStatus<'_> {
+    /// if any part of the burn is yet to be posted in SAP
+    pub fn is_outstanding(&self) -> bool {
+        !(self.part_posted && self.matl_posted)
+    }
+}
+
+/// filters burned parts down to those not yet fully posted in SAP
+pub fn outstanding_burns<'a>(burns: &'a [BurnedPart], docs: &[MaterialDocument]) -> Vec<&'a BurnedPart> {
+    Postings::new(docs)
+        .apply(burns)
+        .into_iter()
+        .filter(BurnStatus::is_outstanding)
+        .map(|status| status.part)
+        .collect()
+}
+
+/// A posted quantity that burns are taken from
+trait Posted: Copy {
+    /// what is left after taking `qty`, if enough was posted to cover it
+    fn take(self, qty: Self) -> Option<Self>;
+}
+
+impl Posted for Qty {
+    fn take(self, qty: Self) -> Option<Self> {
+        self.checked_sub(qty)
+    }
+}
+
+impl Posted for Area {
+    fn take(self, area: Self) -> Option<Self> {
+        if self.as_ft2() + AREA_ROUNDING_FT2 >= area.as_ft2() {
+            // what rounding left short is not carried over to the next burn
+            Some( (self - area).max(Area::default()) )
+        } else {
+            None
+        }
+    }
+}
+
+// takes `qty` from the posted quantity, if enough has been posted to cover it
+fn take<T: Posted>(posted: &mut HashMap<(String, String), T>, key: (String, String), qty: T) -> bool {
+    let Some(remaining) = posted.get_mut(&key) else {
+        return false;
+    };
+
+    match remaining.take(qty) {
+        Some(left) => {
+            *remaining = left;
+            true
+        },
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::{Plant, Quantity, Uom, Wbs};
+
+    fn key() -> (String, String) {
+        ("12345".into(), "50/50W-0500".into())
+    }
+
+    #[test]
+    fn area_rounded_down_by_sap_is_posted() {
+        // 2 burns of 100.0002 FT2, posted for the program rounded to 200.000 FT2
+        let mut posted = HashMap::from([(key(), Area::ft2(200.0))]);
+
+        assert!(take(&mut posted, key(), Area::ft2(100.0002)));
+        assert!(take(&mut posted, key(), Area::ft2(100.0002)));
+        assert!(!take(&mut posted, key(), Area::ft2(0.001)));
+    }
+
+    #[test]
+    fn area_short_by_more_than_rounding_is_outstanding() {
+        let mut posted = HashMap::from([(key(), Area::ft2(100.0))]);
+
+        assert!(!take(&mut posted, key(), Area::ft2(100.001)));
+        assert!(take(&mut posted, key(), Area::ft2(99.5)));
+    }
+
+    #[test]
+    fn pieces_are_taken_exactly() {
+        let mut posted = HashMap::from([(key(), Qty(3))]);
+
+        assert!(take(&mut posted, key(), Qty(2)));
+        assert!(!take(&mut posted, key(), Qty(2)));
+        assert!(take(&mut posted, key(), Qty(1)));
+    }
+
+    fn doc(movement: MovementType, qty: Quantity) -> MaterialDocument {
+        MaterialDocument {
+            movement,
+            matl: "50/50W-0500".into(),
+            plant: Plant::Lancaster,
+            wbs: Wbs::None,
+            qty,
+            date: None,
+            reference: "12345".into(),
+        }
+    }
+
+    #[test]
+    fn by_products_are_skipped() {
+        let postings = Postings::new(&[
+            doc(MovementType::GoodsIssue, Quantity::new(100.0, Uom::SqFt)),
+            doc(MovementType::ByProduct,  Quantity::new(40.0, Uom::SqFt)),
+            doc(MovementType::GoodsIssue, Quantity::new(1.0, Uom::Each)),
+        ]);
+
+        assert_eq!(postings.skipped(), 2);
+        assert_eq!(postings.consumed.get(&key()), Some(&Area::ft2(100.0)));
+    }
+}
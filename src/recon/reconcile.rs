@@ -0,0 +1,269 @@
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use itertools::Itertools;
+
+use crate::api::{BurnPlant, Order, Plant, Qty, Wbs};
+use crate::db::{BurnedPart, PartBurnedQty};
+
+/// A problem found reconciling Sigmanest burns against SAP orders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "finding")]
+pub enum Finding {
+    /// A part was burned that has no order in SAP
+    UnmatchedBurn {
+        /// part name (piece mark)
+        mark: String,
+        /// program the part was burned on
+        program: String,
+        /// plant the part was burned at
+        plant: BurnPlant,
+        /// WBS element of the material burned (`None` for plant stock)
+        #[serde(default)]
+        matl_wbs: Wbs,
+        /// quantity burned
+        qty: Qty,
+    },
+    /// More pieces were burned than there is open order quantity for
+    ShortOrder {
+        /// part name (piece mark)
+        mark: String,
+        /// plant the part was burned at
+        plant: Plant,
+        /// programs the part was burned on
+        #[serde(default)]
+        programs: Vec<String>,
+        /// WBS elements of the part's orders at the plant
+        #[serde(default)]
+        order_wbs: Vec<Wbs>,
+        /// quantity burned
+        burned: Qty,
+        /// open order quantity
+        ordered: Qty,
+    },
+    /// The material was burned from project stock of a job that has no order for the part
+    WbsMismatch {
+        /// part name (piece mark)
+        mark: String,
+        /// program the part was burned on
+        program: String,
+        /// WBS element of the material burned
+        matl_wbs: Wbs,
+        /// WBS elements of the part's orders
+        order_wbs: Vec<Wbs>,
+    },
+    /// The part was burned at a plant that has no order for the part
+    PlantMismatch {
+        /// part name (piece mark)
+        mark: String,
+        /// program the part was burned on
+        program: String,
+        /// WBS element of the material burned (`None` for plant stock)
+        #[serde(default)]
+        matl_wbs: Wbs,
+        /// plant the part was burned at
+        burned: BurnPlant,
+        /// plants the part's orders are in
+        ordered: Vec<Plant>,
+    },
+}
+
+impl Finding {
+    /// the part name (piece mark) the finding is for
+    pub fn mark(&self) -> &str {
+        match self {
+            Self::UnmatchedBurn { mark, .. } |
+            Self::ShortOrder    { mark, .. } |
+            Self::WbsMismatch   { mark, .. } |
+            Self::PlantMismatch { mark, .. } => mark
+        }
+    }
+
+    /// the plant or WBS element the finding is for
+    pub fn scope(&self) -> String {
+        match self {
+            Self::UnmatchedBurn { plant, .. }    => plant.to_string(),
+            Self::ShortOrder    { plant, .. }    => plant.to_string(),
+            Self::WbsMismatch   { matl_wbs, .. } => matl_wbs.to_string(),
+            Self::PlantMismatch { burned, .. }   => burned.to_string(),
+        }
+    }
+
+    /// the plant the finding is for, if known
+    pub fn plant(&self) -> Option<Plant> {
+        match self {
+            Self::UnmatchedBurn { plant, .. }  => plant.known().cloned(),
+            Self::ShortOrder    { plant, .. }  => Some(plant.clone()),
+            Self::PlantMismatch { burned, .. } => burned.known().cloned(),
+            Self::WbsMismatch   { .. }         => None,
+        }
+    }
+
+    /// the quantity out of balance (burned with no order, or burned over the order quantity)
+    pub fn qty(&self) -> Option<Qty> {
+        match self {
+            Self::UnmatchedBurn { qty, .. }             => Some(*qty),
+            Self::ShortOrder    { burned, ordered, .. } => burned.checked_sub(*ordered),
+            Self::WbsMismatch   { .. } |
+            Self::PlantMismatch { .. }                  => None,
+        }
+    }
+
+    /// short name of the kind of finding
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::UnmatchedBurn { .. } => "Unmatched burn",
+            Self::ShortOrder    { .. } => "Short order",
+            Self::WbsMismatch   { .. } => "WBS mismatch",
+            Self::PlantMismatch { .. } => "Plant mismatch",
+        }
+    }
+}
+
+/// reconciles burned parts against SAP orders
+pub fn reconcile(burns: &[BurnedPart], orders: &[Order]) -> Vec<Finding> {
+    reconcile_with_totals(burns, orders, &HashMap::new())
+}
+
+/// reconciles new burns against SAP orders, finding short orders from the
+/// quantity of their marks burned to date
+///
+/// `burned` is the quantity burned to date of the marks of the new burns (see
+/// [`Sndb::get_part_burned_by_plant`](crate::db::Sndb::get_part_burned_by_plant)),
+/// so that an order burned over across several sets of new burns is found.
+pub fn reconcile_to_date(burns: &[BurnedPart], burned: &[PartBurnedQty], orders: &[Order]) -> Vec<Finding> {
+    let mut totals = HashMap::<(&str, Plant), Qty>::new();
+    for part in burned {
+        if let Ok(plant) = Plant::try_from(part.plant.as_str()) {
+            *totals.entry((part.part.as_str(), plant)).or_default() += part.qty;
+        }
+    }
+
+    reconcile_with_totals(burns, orders, &totals)
+}
+
+fn reconcile_with_totals(burns: &[BurnedPart], orders: &[Order], totals: &HashMap<(&str, Plant), Qty>) -> Vec<Finding> {
+    let orders = orders
+        .iter()
+        .map(Order::data)
+        .into_group_map_by(|data| data.mark.as_str());
+
+    let mut findings = Vec::new();
+    // ordered by mark and plant, for a stable report
+    let mut burned = BTreeMap::<(&str, Plant), (Qty, BTreeSet<&str>)>::new();
+    for part in burns {
+        let matl_wbs = part.matl.wbs.as_deref().and_then(|wbs| Wbs::try_from(wbs).ok()).unwrap_or_default();
+
+        let Some(part_orders) = orders.get(part.part.as_str()) else {
+            findings.push(Finding::UnmatchedBurn {
+                mark: part.part.clone(),
+                program: part.program.clone(),
+                plant: part.matl.plant.as_str().into(),
+                matl_wbs,
+                qty: part.qty,
+            });
+            continue;
+        };
+
+        let plant = match BurnPlant::from(part.matl.plant.as_str()) {
+            BurnPlant::Known(plant) if part_orders.iter().any(|data| data.plant == plant) => plant,
+            burned => {
+                findings.push(Finding::PlantMismatch {
+                    mark: part.part.clone(),
+                    program: part.program.clone(),
+                    matl_wbs,
+                    burned,
+                    ordered: part_orders.iter().map(|data| data.plant.clone()).unique().collect(),
+                });
+                continue;
+            }
+        };
+
+        // material from plant stock can be burned for any job
+        if matl_wbs.job().is_some() && !part_orders.iter().any(|data| data.wbs.job() == matl_wbs.job()) {
+            findings.push(Finding::WbsMismatch {
+                mark: part.part.clone(),
+                program: part.program.clone(),
+                order_wbs: part_orders.iter().map(|data| data.wbs.clone()).unique().collect(),
+                matl_wbs,
+            });
+        }
+
+        let (qty, programs) = burned.entry((part.part.as_str(), plant)).or_default();
+        *qty += part.qty;
+        programs.insert(part.program.as_str());
+    }
+
+    for ((mark, plant), (burned, programs)) in burned {
+        let burned = match totals.get(&(mark, plant.clone())) {
+            Some(total) => burned.max(*total),
+            None => burned
+        };
+
+        let plant_orders: Vec<_> = orders[mark]
+            .iter()
+            .filter(|data| data.plant == plant)
+            .collect();
+        let ordered = plant_orders.iter().map(|data| data.qty).sum();
+
+        if burned > ordered {
+            findings.push(Finding::ShortOrder {
+                mark: mark.into(),
+                plant,
+                programs: programs.into_iter().map(String::from).collect(),
+                order_wbs: plant_orders.iter().map(|data| data.wbs.clone()).unique().collect(),
+                burned,
+                ordered,
+            });
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::OrderData;
+    use crate::db::MaterialData;
+
+    fn burn(qty: u32) -> BurnedPart {
+        BurnedPart {
+            part: "1200001A-B1".into(),
+            qty: Qty(qty),
+            matl: MaterialData {
+                matl: "50/50W-0500".into(),
+                wbs: None,
+                loc: "K2".into(),
+                plant: "HS01".into(),
+                area: Default::default(),
+            },
+            program: "12346".into(),
+            repeat_id: 1,
+            packet_id: 2,
+            machine: "Gemini".into(),
+            sheet: "S2".into(),
+            archived: None,
+        }
+    }
+
+    fn burned(plant: &str, qty: u32) -> PartBurnedQty {
+        PartBurnedQty { part: "1200001A-B1".into(), plant: plant.into(), wbs: None, qty: Qty(qty) }
+    }
+
+    #[test]
+    fn orders_burned_over_across_polls_are_short() {
+        let orders = [Order::PlannedOrder(OrderData {
+            id: 1, mark: "1200001A-B1".into(), qty: Qty(4), wbs: Wbs::try_from("D-1200001-10002").unwrap(), plant: Plant::Lancaster
+        })];
+
+        // 3 burned in an earlier poll, 2 in this one
+        assert!(reconcile(&[burn(2)], &orders).is_empty());
+
+        let findings = reconcile_to_date(&[burn(2)], &[burned("HS01", 5), burned("HS02", 3)], &orders);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind(), "Short order");
+        assert_eq!(findings[0].qty(), Some(Qty(1)));
+    }
+}
@@ -0,0 +1,222 @@
+
+use std::collections::BTreeMap;
+
+use crate::api::Area;
+use crate::db::{BurnedPart, Program, Remnant};
+
+/// How the area of a burned sheet was used
+#[derive(Debug, Clone, Serialize)]
+pub struct SheetUsage {
+    /// the name of the program
+    pub program: String,
+    /// the repeat of the program
+    pub repeat_id: i32,
+    /// the machine the program was burned on
+    pub machine: String,
+    /// the name of the sheet burned
+    pub sheet: String,
+    /// the sheet material number
+    pub matl: String,
+    /// area of the sheet
+    pub sheet_area: Area,
+    /// area consumed by the burned parts
+    pub consumed: Area,
+    /// area returned to stock as remnants
+    pub remnant_area: Area,
+    /// names of the remnants cut from the sheet
+    pub remnants: Vec<String>,
+}
+
+impl SheetUsage {
+    /// area that was neither consumed by parts nor returned as a remnant
+    pub fn scrap(&self) -> Area {
+        (self.sheet_area - self.consumed - self.remnant_area).max(Area::default())
+    }
+
+    /// scrap as a fraction of the sheet area
+    pub fn scrap_pct(&self) -> f64 {
+        pct(self.scrap(), self.sheet_area)
+    }
+}
+
+/// Scrap totals for a machine
+#[derive(Debug, Clone, Serialize)]
+pub struct MachineScrap {
+    /// the machine name
+    pub machine: String,
+    /// number of sheets burned
+    pub sheets: usize,
+    /// total area of the sheets burned
+    pub sheet_area: Area,
+    /// total area consumed by parts
+    pub consumed: Area,
+    /// total area returned to stock as remnants
+    pub remnant_area: Area,
+    /// total scrap area
+    pub scrap: Area,
+}
+
+impl MachineScrap {
+    /// scrap as a fraction of the sheet area
+    pub fn scrap_pct(&self) -> f64 {
+        pct(self.scrap, self.sheet_area)
+    }
+}
+
+fn pct(area: Area, of: Area) -> f64 {
+    match of.as_in2() {
+        total if total > 0.0 => area.as_in2() / total,
+        _ => 0.0
+    }
+}
+
+/// computes how the sheet of each program was used
+pub fn sheet_usage(programs: &[Program], burns: &[BurnedPart], remnants: &[Remnant]) -> Vec<SheetUsage> {
+    programs
+        .iter()
+        .map(|program| {
+            let remnants: Vec<&Remnant> = remnants
+                .iter()
+                .filter(|remnant| remnant.program == program.name && remnant.repeat_id == program.repeat_id)
+                .collect();
+
+            SheetUsage {
+                program: program.name.clone(),
+                repeat_id: program.repeat_id,
+                machine: program.machine.clone(),
+                sheet: program.sheet.clone(),
+                matl: program.matl.matl.clone(),
+                sheet_area: program.matl.area,
+                consumed: program.parts_burned(burns).map(|part| part.matl.area).sum(),
+                remnant_area: remnants.iter().map(|remnant| remnant.matl.area).sum(),
+                remnants: remnants.iter().map(|remnant| remnant.name.clone()).collect(),
+            }
+        })
+        .collect()
+}
+
+/// totals scrap by the machine the sheets were burned on
+pub fn scrap_by_machine(usage: &[SheetUsage]) -> Vec<MachineScrap> {
+    let mut machines = BTreeMap::<&str, MachineScrap>::new();
+    for sheet in usage {
+        let totals = machines.entry(sheet.machine.as_str()).or_insert_with(|| MachineScrap {
+            machine: sheet.machine.clone(),
+            sheets: 0,
+            sheet_area: Area::default(),
+            consumed: Area::default(),
+            remnant_area: Area::default(),
+            scrap: Area::default(),
+        });
+
+        totals.sheets += 1;
+        totals.sheet_area += sheet.sheet_area;
+        totals.consumed += sheet.consumed;
+        totals.remnant_area += sheet.remnant_area;
+        totals.scrap += sheet.scrap();
+    }
+
+    machines.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::Qty;
+    use crate::db::MaterialData;
+
+    fn matl(area: f64) -> MaterialData {
+        MaterialData {
+            matl: "50/50W-0500".into(),
+            wbs: None,
+            loc: "PROD".into(),
+            plant: "HS01".into(),
+            area: Area::ft2(area),
+        }
+    }
+
+    fn program(name: &str, machine: &str) -> Program {
+        Program {
+            name: name.into(),
+            repeat_id: 1,
+            machine: machine.into(),
+            sheet: format!("S{}", name),
+            matl: matl(100.0),
+            parts: Qty(2),
+            nested_area: Area::ft2(60.0),
+            archived: None,
+        }
+    }
+
+    fn burn(program: &str, area: f64) -> BurnedPart {
+        BurnedPart {
+            part: "1200001A-B1".into(),
+            qty: Qty(1),
+            matl: matl(area),
+            program: program.into(),
+            repeat_id: 1,
+            packet_id: 1,
+            machine: "Gemini".into(),
+            sheet: format!("S{}", program),
+            archived: None,
+        }
+    }
+
+    fn remnant(name: &str, program: &str, area: f64) -> Remnant {
+        Remnant {
+            name: name.into(),
+            program: program.into(),
+            repeat_id: 1,
+            matl: matl(area),
+        }
+    }
+
+    #[test]
+    fn sheet_usage_credits_parts_and_remnants() {
+        let programs = [program("1", "Gemini"), program("2", "Gemini"), program("3", "Titan")];
+        let burns = [burn("1", 30.0), burn("1", 20.0), burn("3", 90.0)];
+        let remnants = [remnant("R1", "1", 40.0), remnant("R9", "9", 25.0)];
+
+        let usage = sheet_usage(&programs, &burns, &remnants);
+
+        assert_eq!(usage.len(), 3);
+
+        assert_eq!(usage[0].consumed, Area::ft2(50.0));
+        assert_eq!(usage[0].remnant_area, Area::ft2(40.0));
+        assert_eq!(usage[0].remnants, ["R1"]);
+        assert_eq!(usage[0].scrap(), Area::ft2(10.0));
+
+        // no burns and no remnants: the whole sheet is scrap
+        assert_eq!(usage[1].consumed, Area::default());
+        assert!(usage[1].remnants.is_empty());
+        assert_eq!(usage[1].scrap(), Area::ft2(100.0));
+        assert_eq!(usage[1].scrap_pct(), 1.0);
+
+        // the remnant of program 9 is not credited to any sheet
+        assert_eq!(usage[2].remnant_area, Area::default());
+        assert_eq!(usage[2].scrap(), Area::ft2(10.0));
+    }
+
+    #[test]
+    fn scrap_is_totaled_by_machine() {
+        let programs = [program("1", "Gemini"), program("2", "Gemini"), program("3", "Titan")];
+        let burns = [burn("1", 30.0), burn("1", 20.0), burn("3", 90.0)];
+        let remnants = [remnant("R1", "1", 40.0)];
+
+        let machines = scrap_by_machine(&sheet_usage(&programs, &burns, &remnants));
+
+        assert_eq!(machines.len(), 2);
+
+        assert_eq!(machines[0].machine, "Gemini");
+        assert_eq!(machines[0].sheets, 2);
+        assert_eq!(machines[0].sheet_area, Area::ft2(200.0));
+        assert_eq!(machines[0].consumed, Area::ft2(50.0));
+        assert_eq!(machines[0].remnant_area, Area::ft2(40.0));
+        assert_eq!(machines[0].scrap, Area::ft2(110.0));
+
+        assert_eq!(machines[1].machine, "Titan");
+        assert_eq!(machines[1].sheets, 1);
+        assert_eq!(machines[1].scrap, Area::ft2(10.0));
+        assert_eq!(machines[1].scrap_pct(), 0.1);
+    }
+}
@@ -0,0 +1,274 @@
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+
+use ftlog::warn;
+use itertools::Itertools;
+
+use crate::api::{Area, StockItem, Wbs};
+use crate::db::{BurnedPart, SheetStock};
+
+/// material, plant, storage location and WBS that stock is held under
+type StockKey = (String, String, String, Wbs);
+
+/// material, plant and WBS that SAP and Sigmanest stock are compared under
+///
+/// Storage locations are not part of the key: SAP has the MB52 storage location
+/// and Sigmanest its own yard locations, which never line up.
+type InventoryKey = (String, String, Wbs);
+
+/// A program whose material consumption is not covered by SAP stock
+#[derive(Debug, Clone, Serialize)]
+pub struct StockShortage {
+    /// the name of the program
+    pub program: String,
+    /// material number
+    pub matl: String,
+    /// plant
+    pub plant: String,
+    /// storage location
+    pub loc: String,
+    /// WBS element the material is consumed from
+    pub wbs: Wbs,
+    /// area consumed by the program
+    pub required: Area,
+    /// area left in SAP stock when the program is applied
+    pub available: Area,
+}
+
+impl StockShortage {
+    /// area not covered by SAP stock
+    pub fn shortage(&self) -> Area {
+        self.required - self.available
+    }
+}
+
+/// checks that the material consumed by each program is covered by SAP stock
+///
+/// Programs are applied against stock in name order, so that when several
+/// programs draw from the same stock the later programs are the ones flagged.
+pub fn check_stock(burns: &[BurnedPart], stock: &[StockItem]) -> Vec<StockShortage> {
+    let mut available = HashMap::<StockKey, Area>::new();
+    for item in stock {
+        let key = (item.matl.clone(), item.plant.to_string(), item.loc.clone(), item.wbs.clone());
+        *available.entry(key).or_default() += item.qty;
+    }
+
+    let programs = burns.iter().into_group_map_by(|part| part.program.as_str());
+
+    let mut shortages = Vec::new();
+    for (program, parts) in programs.into_iter().sorted_by_key(|(program, _)| *program) {
+        let mut required = HashMap::<StockKey, Area>::new();
+        for part in parts {
+            let wbs = match part.matl.wbs.as_deref().map(Wbs::try_from) {
+                Some(Ok(wbs)) => wbs,
+                None => Wbs::None,
+                Some(Err(e)) => {
+                    warn!("skipping stock check for part `{}` in program `{}`: {}", part.part, program, e);
+                    continue;
+                }
+            };
+
+            let key = (part.matl.matl.clone(), part.matl.plant.clone(), part.matl.loc.clone(), wbs);
+            *required.entry(key).or_default() += part.matl.area;
+        }
+
+        for (key, area) in required.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+            let remaining = available.entry(key.clone()).or_default();
+            if area > *remaining {
+                let (matl, plant, loc, wbs) = key;
+                shortages.push(StockShortage {
+                    program: program.into(),
+                    matl, plant, loc, wbs,
+                    required: area,
+                    available: remaining.max(Area::default()),
+                });
+            }
+
+            *remaining -= area;
+        }
+    }
+
+    shortages
+}
+
+/// How SAP and Sigmanest stock disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum StockStatus {
+    /// the stock is in SAP, but there are no sheets in Sigmanest
+    OnlyInSap,
+    /// there are sheets in Sigmanest, but no stock in SAP
+    OnlyInSigmanest,
+    /// both have stock, but the areas do not agree
+    AreaMismatch,
+}
+
+impl Display for StockStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use StockStatus::*;
+
+        let name = match self {
+            OnlyInSap       => "Only in SAP",
+            OnlyInSigmanest => "Only in Sigmanest",
+            AreaMismatch    => "Area mismatch",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Stock that SAP and Sigmanest disagree on
+#[derive(Debug, Clone, Serialize)]
+pub struct StockDiff {
+    /// material number
+    pub matl: String,
+    /// plant
+    pub plant: String,
+    /// WBS element the stock is held under
+    pub wbs: Wbs,
+    /// how the stock disagrees
+    pub status: StockStatus,
+    /// unrestricted-use area in SAP
+    pub sap: Area,
+    /// area of the sheets in Sigmanest
+    pub sigmanest: Area,
+    /// storage locations of the SAP stock
+    pub sap_locs: Vec<String>,
+    /// names of the sheets in Sigmanest
+    pub sheets: Vec<String>,
+}
+
+/// compares the Sigmanest inventory against SAP stock
+///
+/// Stock is flagged if it is only in one of the systems, or if the areas
+/// differ by more than `tolerance`.
+pub fn compare_stock(sheets: &[SheetStock], stock: &[StockItem], tolerance: Area) -> Vec<StockDiff> {
+    let mut sap = BTreeMap::<InventoryKey, (Area, Vec<String>)>::new();
+    for item in stock.iter().filter(|item| item.qty > Area::default()) {
+        let key = (item.matl.clone(), item.plant.to_string(), item.wbs.clone());
+        let (area, locs) = sap.entry(key).or_default();
+        *area += item.qty;
+        if !locs.contains(&item.loc) {
+            locs.push(item.loc.clone());
+        }
+    }
+
+    let mut sn = BTreeMap::<InventoryKey, (Area, Vec<String>)>::new();
+    for sheet in sheets {
+        let wbs = match sheet.matl.wbs.as_deref().map(Wbs::try_from) {
+            Some(Ok(wbs)) => wbs,
+            None => Wbs::None,
+            Some(Err(e)) => {
+                warn!("skipping stock comparison for sheet `{}`: {}", sheet.sheet, e);
+                continue;
+            }
+        };
+
+        let key = (sheet.matl.matl.clone(), sheet.matl.plant.clone(), wbs);
+        let (area, names) = sn.entry(key).or_default();
+        *area += sheet.matl.area;
+        names.push(sheet.sheet.clone());
+    }
+
+    let mut diffs = Vec::new();
+    for key in sap.keys().chain(sn.keys()).unique().sorted().cloned().collect::<Vec<_>>() {
+        let (sap_area, locs) = match sap.remove(&key) {
+            Some((area, locs)) => (Some(area), locs),
+            None => (None, Vec::new())
+        };
+        let (sn_area, names) = match sn.remove(&key) {
+            Some((area, names)) => (Some(area), names),
+            None => (None, Vec::new())
+        };
+
+        let status = match (sap_area, sn_area) {
+            (Some(_), None) => StockStatus::OnlyInSap,
+            (None, Some(_)) => StockStatus::OnlyInSigmanest,
+            (Some(a), Some(b)) if (a - b).as_in2().abs() > tolerance.as_in2() => StockStatus::AreaMismatch,
+            _ => continue
+        };
+
+        let (matl, plant, wbs) = key;
+        diffs.push(StockDiff {
+            matl, plant, wbs, status,
+            sap: sap_area.unwrap_or_default(),
+            sigmanest: sn_area.unwrap_or_default(),
+            sap_locs: locs,
+            sheets: names,
+        });
+    }
+
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::{Plant, Qty};
+    use crate::db::MaterialData;
+
+    #[test]
+    fn stock_in_different_locations_matches() {
+        let stock = [StockItem {
+            matl: "50/50W-0500".into(),
+            plant: Plant::Lancaster,
+            loc: "PROD".into(),
+            wbs: Wbs::None,
+            qty: Area::ft2(100.0),
+        }];
+
+        let sheets = [SheetStock {
+            sheet: "S1".into(),
+            matl: MaterialData {
+                matl: "50/50W-0500".into(),
+                wbs: None,
+                loc: "K2".into(),
+                plant: "HS01".into(),
+                area: Area::ft2(100.0),
+            },
+            length: 120.0,
+            width: 120.0,
+            qty: Qty(1),
+            remnant: false,
+        }];
+
+        assert!(compare_stock(&sheets, &stock, Area::default()).is_empty());
+    }
+
+    fn burn(plant: &str, wbs: Option<&str>) -> BurnedPart {
+        BurnedPart {
+            part: "1200001A-B1".into(),
+            qty: Qty(1),
+            matl: MaterialData {
+                matl: "50/50W-0500".into(),
+                wbs: wbs.map(String::from),
+                loc: "PROD".into(),
+                plant: plant.into(),
+                area: Area::ft2(10.0),
+            },
+            program: "12345".into(),
+            repeat_id: 1,
+            packet_id: 1,
+            machine: "Gemini".into(),
+            sheet: "S1".into(),
+            archived: None,
+        }
+    }
+
+    #[test]
+    fn shortages_of_a_material_are_ordered_by_plant_and_wbs() {
+        let burns = [burn("HS02", None), burn("HS01", Some("D-1200001-10002")), burn("HS01", None)];
+
+        let shortages: Vec<_> = check_stock(&burns, &[])
+            .into_iter()
+            .map(|shortage| (shortage.plant, shortage.wbs.to_string()))
+            .collect();
+
+        assert_eq!(shortages, [
+            ("HS01".into(), String::new()),
+            ("HS01".into(), "D-1200001-10002".into()),
+            ("HS02".into(), String::new()),
+        ]);
+    }
+}
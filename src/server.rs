@@ -0,0 +1,331 @@
+
+//! HTTP API exposing burns, orders and findings as JSON
+//!
+//! Served by the watch daemon with `--listen` (needs the `http` feature).
+//!
+//! | route              | response                                                   |
+//! |--------------------|------------------------------------------------------------|
+//! | `/health`          | status of the last poll for new burns                      |
+//! | `/burns`           | parts burned (filtered by `?part=&program=&job=&plant=`)   |
+//! | `/orders`          | orders in the COHV export (`?mark=`)                       |
+//! | `/findings`        | burns reconciled against the COHV export                   |
+//! | `/programs/{name}` | sheets of a program and the parts burned on them           |
+//!
+//! Burns are for the days `?from=&to=` (`YYYY-MM-DD`, `to` is the day after the
+//! last day), or the previous Sunday-to-Saturday week if not given (the same
+//! default as the recon binary). Errors reading Sigmanest or the COHV export
+//! are returned as `{"error": message}`.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::get;
+use axum::Router;
+use ftlog::info;
+use time::OffsetDateTime;
+use tokio::net::TcpListener;
+
+use crate::api::Order;
+use crate::dates::DateRange;
+use crate::db::{BurnFilter, BurnedPart, HighWaterMark, Program, Sndb};
+use crate::excel::cohv::parse_cohv_xl;
+use crate::recon::{self, Finding};
+use crate::{Error, Result};
+
+/// Status of the watch daemon, updated after each poll
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Health {
+    /// when the last poll finished
+    pub last_poll: Option<OffsetDateTime>,
+    /// error of the last poll, if it failed
+    pub last_error: Option<String>,
+    /// last position in the Sigmanest archive processed
+    pub mark: HighWaterMark,
+}
+
+impl Health {
+    /// records the result of a poll
+    pub fn polled(&mut self, result: &Result<()>, mark: &HighWaterMark) {
+        self.last_poll = Some(OffsetDateTime::now_utc());
+        self.last_error = result.as_ref().err().map(ToString::to_string);
+        self.mark = mark.clone();
+    }
+}
+
+/// What the API is served from
+#[derive(Debug, Clone)]
+pub struct ApiState {
+    sn: Sndb,
+    cohv: Option<PathBuf>,
+    health: Arc<RwLock<Health>>,
+}
+
+impl ApiState {
+    /// serves burns from `sn` and orders from the COHV export at `cohv`, if given
+    pub fn new(sn: Sndb, cohv: Option<PathBuf>) -> Self {
+        Self { sn, cohv, health: Default::default() }
+    }
+
+    /// the health reported by `/health`, for the daemon to update
+    pub fn health(&self) -> Arc<RwLock<Health>> {
+        self.health.clone()
+    }
+
+    /// the orders in the COHV export
+    ///
+    /// The export is read on each request, so it can be refreshed while serving.
+    async fn orders(&self) -> std::result::Result<Vec<Order>, ApiError> {
+        let cohv = self.cohv
+            .clone()
+            .ok_or_else(|| ApiError(StatusCode::SERVICE_UNAVAILABLE, "no COHV export is configured".into()))?;
+
+        tokio::task::spawn_blocking(move || parse_cohv_xl(cohv))
+            .await
+            .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map_err(ApiError::from)
+    }
+}
+
+/// An error response
+struct ApiError(StatusCode, String);
+
+impl From<Error> for ApiError {
+    fn from(err: Error) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+#[derive(Debug, Deserialize)]
+struct OrderQuery {
+    mark: Option<String>,
+}
+
+/// A program's sheets and the parts burned on them
+#[derive(Debug, Serialize)]
+struct ProgramDetail {
+    name: String,
+    sheets: Vec<Program>,
+    parts: Vec<BurnedPart>,
+}
+
+async fn health(State(state): State<ApiState>) -> (StatusCode, Json<Health>) {
+    let health = state.health.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let status = match health.last_error {
+        Some(_) => StatusCode::SERVICE_UNAVAILABLE,
+        None    => StatusCode::OK,
+    };
+
+    (status, Json(health))
+}
+
+async fn burns(State(state): State<ApiState>, Query(range): Query<DateRange>, Query(filter): Query<BurnFilter>) -> ApiResult<Vec<BurnedPart>> {
+    let burns = range.get_burns(&state.sn).await?;
+
+    Ok( Json(burns.into_iter().filter(|part| filter.matches(part)).collect()) )
+}
+
+async fn orders(State(state): State<ApiState>, Query(query): Query<OrderQuery>) -> ApiResult<Vec<Order>> {
+    let orders = state.orders().await?;
+
+    Ok( Json(orders.into_iter().filter(|order| query.mark.as_ref().is_none_or(|mark| *mark == order.data().mark)).collect()) )
+}
+
+async fn findings(State(state): State<ApiState>, Query(range): Query<DateRange>) -> ApiResult<Vec<Finding>> {
+    let orders = state.orders().await?;
+    let burns = range.get_burns(&state.sn).await?;
+
+    Ok( Json(recon::reconcile(&burns, &orders)) )
+}
+
+async fn program(State(state): State<ApiState>, Path(name): Path<String>, Query(range): Query<DateRange>) -> ApiResult<ProgramDetail> {
+    let (from, to) = range.bounds();
+
+    let mut sheets = state.sn.get_nested_programs().await?;
+    sheets.extend(state.sn.get_programs(from, to).await?);
+    sheets.retain(|sheet| sheet.name == name);
+
+    let mut parts = range.get_burns(&state.sn).await?;
+    parts.retain(|part| part.program == name);
+
+    if sheets.is_empty() && parts.is_empty() {
+        return Err( ApiError(StatusCode::NOT_FOUND, format!("program `{}` not found from {} to {}", name, from, to)) );
+    }
+
+    Ok( Json(ProgramDetail { name, sheets, parts }) )
+}
+
+/// the routes of the API
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/burns", get(burns))
+        .route("/orders", get(orders))
+        .route("/findings", get(findings))
+        .route("/programs/{name}", get(program))
+        .with_state(state)
+}
+
+/// binds the address to serve the API on
+pub async fn listen(addr: SocketAddr) -> Result<TcpListener> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(Error::io(format!("binding the http api to {}", addr)))?;
+
+    info!("serving the http api on {}", addr);
+    Ok(listener)
+}
+
+/// serves the API until the task is dropped
+pub async fn serve(listener: TcpListener, state: ApiState) -> Result<()> {
+    axum::serve(listener, router(state))
+        .await
+        .map_err(Error::io("serving the http api"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use time::{Date, Month, PrimitiveDateTime};
+    use tower::ServiceExt;
+
+    use crate::db::query::{FromRow, SqlRow, Value};
+    use crate::db::snapshot::Snapshot;
+
+    fn october(day: u8) -> PrimitiveDateTime {
+        Date::from_calendar_date(2026, Month::October, day).unwrap().midnight()
+    }
+
+    fn row(columns: &[&str], values: Vec<(&str, Value)>) -> SqlRow {
+        let values = columns
+            .iter()
+            .map(|col| values.iter().find(|(name, _)| name == col).map(|(_, val)| val.clone()).unwrap_or(Value::Null))
+            .collect();
+
+        SqlRow::new(columns.iter().map(|col| col.to_string()).collect(), values)
+    }
+
+    fn material() -> Vec<(&'static str, Value)> {
+        vec![
+            ("MaterialMaster", Value::Str("50/50W-0500".into())),
+            ("Location", Value::Str("K2".into())),
+            ("Plant", Value::Str("HS01".into())),
+            ("Area", Value::Float(100.0)),
+        ]
+    }
+
+    /// a burn of 2 pieces on program 12345 on 2026-10-06, and program 12346 nested
+    fn state() -> ApiState {
+        let range = [
+            ("from".to_string(), Value::DateTime(october(5))),
+            ("to".to_string(), Value::DateTime(october(12))),
+        ];
+
+        let mut burn = vec![
+            ("Part", Value::Str("1200001A-B1".into())),
+            ("Qty", Value::Int(2)),
+            ("Program", Value::Str("12345".into())),
+            ("RepeatID", Value::Int(1)),
+            ("ArchivePacketID", Value::Int(1)),
+            ("Machine", Value::Str("Gemini".into())),
+            ("Sheet", Value::Str("S1".into())),
+            ("ArcDateTime", Value::DateTime(october(6))),
+        ];
+        burn.extend(material());
+
+        let program = |name: &str, sheet: &str| {
+            let mut program = vec![
+                ("Program", Value::Str(name.into())),
+                ("RepeatID", Value::Int(1)),
+                ("Machine", Value::Str("Gemini".into())),
+                ("Sheet", Value::Str(sheet.into())),
+                ("Qty", Value::Int(2)),
+                ("NestedArea", Value::Float(50.0)),
+            ];
+            program.extend(material());
+            row(Program::COLUMNS, program)
+        };
+
+        let mut snapshot = Snapshot::default();
+        snapshot.insert("get_parts_burned.sql", &range, &[row(BurnedPart::COLUMNS, burn)]);
+        snapshot.insert("get_programs.sql", &range, &[program("12345", "S1")]);
+        snapshot.insert("get_nested_programs.sql", &[], &[program("12346", "S2")]);
+
+        ApiState::new(Sndb::from_snapshot(snapshot), None)
+    }
+
+    async fn get(uri: &str) -> (StatusCode, serde_json::Value) {
+        let resp = router(state())
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn health_before_the_first_poll() {
+        let (status, body) = get("/health").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["last_poll"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn burns_for_a_date_range() {
+        let (status, body) = get("/burns?from=2026-10-05&to=2026-10-12").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["part"], "1200001A-B1");
+
+        let (_, body) = get("/burns?from=2026-10-05&to=2026-10-12&part=1200001A-B2").await;
+        assert!(body.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn program_with_its_burns() {
+        let (status, body) = get("/programs/12345?from=2026-10-05&to=2026-10-12").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["sheets"][0]["sheet"], "S1");
+        assert_eq!(body["parts"][0]["part"], "1200001A-B1");
+
+        let (status, body) = get("/programs/12346?from=2026-10-05&to=2026-10-12").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["parts"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_program_is_not_found() {
+        let (status, body) = get("/programs/99999?from=2026-10-05&to=2026-10-12").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["error"].as_str().unwrap().contains("99999"));
+    }
+
+    #[tokio::test]
+    async fn orders_without_a_cohv_export() {
+        let (status, body) = get("/orders").await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "no COHV export is configured");
+    }
+}
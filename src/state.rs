@@ -0,0 +1,58 @@
+//! JSON files kept between runs (high-water marks, snapshots, sent notifications)
+
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Error, Result};
+
+/// loads the JSON in `path`, or the default if the file does not exist yet
+pub(crate) fn load_or_default<T: DeserializeOwned + Default>(path: &Path, what: &'static str) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let text = fs::read_to_string(path)
+        .map_err(Error::io(format!("reading {} `{}`", what, path.display())))?;
+
+    serde_json::from_str(&text).map_err(|e| Error::parse(what, e))
+}
+
+/// saves `value` as pretty JSON to `path`, describing it as `what` in errors
+///
+/// The file is written to a temporary file first so an interrupted save does
+/// not leave a corrupt file behind.
+pub(crate) fn save<T: Serialize>(value: &T, path: &Path, what: &'static str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let text = serde_json::to_string_pretty(value).map_err(|e| Error::parse(what, e))?;
+
+    fs::write(&tmp, text)
+        .map_err(Error::io(format!("writing {} `{}`", what, tmp.display())))?;
+    fs::rename(&tmp, path)
+        .map_err(Error::io(format!("replacing {} `{}`", what, path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn saved_state_loads_back() {
+        let path = std::env::temp_dir().join(format!("sap-watch-state-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let empty: BTreeMap<String, i64> = load_or_default(&path, "test state").unwrap();
+        assert!(empty.is_empty());
+
+        let state = BTreeMap::from([("1200001A-B1".to_string(), 2)]);
+        save(&state, &path, "test state").unwrap();
+        assert_eq!(load_or_default::<BTreeMap<String, i64>>(&path, "test state").unwrap(), state);
+        assert!(!path.with_extension("tmp").exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+}